@@ -0,0 +1,156 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::collections::HashMap;
+
+use randorank::glicko::MultiPeriod;
+use randorank::math;
+use randorank::simulate::Rng;
+use test::{black_box, Bencher};
+
+struct Season {
+    players: HashMap<String, HashMap<String, f64>>,
+    races: Vec<HashMap<String, f64>>,
+}
+
+fn season(num_players: usize, num_races: usize, min_size: usize, max_size: usize) -> Season {
+    let mut rng = Rng::new(1);
+    let skill: Vec<f64> = (0..num_players).map(|_| rng.normal()).collect();
+    let mut players: HashMap<String, HashMap<String, f64>> = HashMap::with_capacity(num_players);
+    for (i, s) in skill.iter().enumerate() {
+        let mut player: HashMap<String, f64> = HashMap::with_capacity(6);
+        player.insert("rating".to_string(), 1500f64 + 200f64 * s);
+        player.insert("deviation".to_string(), 60f64 + 100f64 * rng.uniform());
+        player.insert("volatility".to_string(), 0.06);
+        player.insert("variance".to_string(), 0f64);
+        player.insert("delta".to_string(), 0f64);
+        player.insert("inactive_periods".to_string(), 0f64);
+        players.insert(format!("player {}", i), player);
+    }
+
+    // seeds finish around an hour and a half, better runners finish faster
+    // and about one entrant in twelve forfeits. someone always finishes
+    let mut races: Vec<HashMap<String, f64>> = Vec::with_capacity(num_races);
+    for _ in 0..num_races {
+        let size = min_size + rng.below(max_size - min_size + 1);
+        let mut race: HashMap<String, f64> = HashMap::with_capacity(size);
+        while race.len() < size {
+            let i = rng.below(num_players);
+            let time = if !race.is_empty() && rng.uniform() < 1f64 / 12f64 {
+                f64::NAN
+            } else {
+                (5400f64 * (1f64 - 0.08 * skill[i] + 0.05 * rng.normal())).round()
+            };
+            race.insert(format!("player {}", i), time);
+        }
+        races.push(race);
+    }
+
+    Season { players, races }
+}
+
+// weekly races of a handful of runners each
+fn small_season() -> Season {
+    season(500, 1000, 2, 8)
+}
+
+// a couple of big community races a week
+fn large_season() -> Season {
+    season(2000, 20, 100, 100)
+}
+
+// a big category over a long period, thousands of established players
+fn thousands_season() -> Season {
+    season(3000, 1500, 2, 20)
+}
+
+fn period(season: &Season) -> MultiPeriod {
+    let mut period = MultiPeriod::default();
+    period.add_player_map(season.players.clone()).unwrap();
+    period
+        .add_race_list(season.races.clone(), "weekly", None)
+        .unwrap();
+
+    period
+}
+
+#[bench]
+fn normalize_race_small(b: &mut Bencher) {
+    let races = small_season().races;
+    b.iter(|| {
+        for race in races.iter() {
+            black_box(math::normalize_race(race, &1.5));
+        }
+    });
+}
+
+#[bench]
+fn normalize_race_large(b: &mut Bencher) {
+    let races = large_season().races;
+    b.iter(|| {
+        for race in races.iter() {
+            black_box(math::normalize_race(race, &1.5));
+        }
+    });
+}
+
+#[bench]
+fn get_sigma(b: &mut Bencher) {
+    let mut rng = Rng::new(2);
+    let inputs: Vec<(f64, f64, f64)> = (0..1000)
+        .map(|_| {
+            let phi = 0.3 + 1.5 * rng.uniform();
+            let v = 0.5 + 20f64 * rng.uniform();
+            let delta = 3f64 * rng.normal();
+            (phi, delta, v)
+        })
+        .collect();
+    b.iter(|| {
+        for &(phi, delta, v) in inputs.iter() {
+            let _ = black_box(math::get_sigma(0.2, phi, 0.06, delta, v));
+        }
+    });
+}
+
+#[bench]
+fn add_races_small(b: &mut Bencher) {
+    let season = small_season();
+    b.iter(|| black_box(period(&season)));
+}
+
+#[bench]
+fn add_races_large(b: &mut Bencher) {
+    let season = large_season();
+    b.iter(|| black_box(period(&season)));
+}
+
+#[bench]
+fn make_pairings_small(b: &mut Bencher) {
+    let period = period(&small_season());
+    b.iter(|| black_box(period.num_pairings()));
+}
+
+#[bench]
+fn make_pairings_large(b: &mut Bencher) {
+    let period = period(&large_season());
+    b.iter(|| black_box(period.num_pairings()));
+}
+
+#[bench]
+fn rank_small(b: &mut Bencher) {
+    let period = period(&small_season());
+    b.iter(|| black_box(period.rankings(true).len()));
+}
+
+#[bench]
+fn rank_large(b: &mut Bencher) {
+    let period = period(&large_season());
+    b.iter(|| black_box(period.rankings(true).len()));
+}
+
+#[bench]
+fn rank_thousands(b: &mut Bencher) {
+    let period = period(&thousands_season());
+    b.iter(|| black_box(period.rankings(true).len()));
+}
@@ -0,0 +1,105 @@
+#ifndef RANDORANK_H
+#define RANDORANK_H
+
+/* Generated by cbindgen from src/capi.rs. Don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A rating period. Create one with randorank_period_new() or
+// randorank_period_from_preset() and free it with randorank_period_free().
+typedef struct RandorankPeriod RandorankPeriod;
+
+// Create an empty period with the default constants.
+struct RandorankPeriod *randorank_period_new(void);
+
+// Create a period using one of the built-in presets, "alttpr" or "glicko2".
+// Returns null if there's no preset with that name.
+//
+// # Safety
+//
+// name must be a valid nul-terminated string.
+struct RandorankPeriod *randorank_period_from_preset(const char *name);
+
+// Free a period. Passing null does nothing.
+//
+// # Safety
+//
+// period must be null or a period from this library that hasn't been freed.
+void randorank_period_free(struct RandorankPeriod *period);
+
+// Set several constants at once from a JSON object of names to values.
+// Returns 0, or -1 on error.
+//
+// # Safety
+//
+// period must be a live period and constants a valid nul-terminated string.
+int randorank_set_constants(struct RandorankPeriod *period, const char *constants);
+
+// Add players with ratings from an earlier period, as returned by
+// randorank_rank(). Returns 0, or -1 on error.
+//
+// # Safety
+//
+// period must be a live period and players a valid nul-terminated string.
+int randorank_add_players(struct RandorankPeriod *period, const char *players);
+
+// Add a JSON race or list of races, each an object of names to times in
+// seconds with null for forfeits. event is casual, weekly, qualifier or
+// tournament, or null for weekly. timestamps is null or a JSON list of each
+// race's unix time. Returns 0, or -1 on error.
+//
+// # Safety
+//
+// period must be a live period, races a valid nul-terminated string and
+// event and timestamps null or valid nul-terminated strings.
+int randorank_add_races(struct RandorankPeriod *period,
+                        const char *races,
+                        const char *event,
+                        const char *timestamps);
+
+// Rank every player and return a JSON object of names to their ratings.
+// With end set to 0, runners who raced get mid-period values instead: their
+// pre-period volatility and rated_at, and their variance and delta so far.
+// Returns null on error. Free the result with randorank_string_free().
+//
+// # Safety
+//
+// period must be a live period.
+char *randorank_rank(struct RandorankPeriod *period, int end);
+
+// Add races like randorank_add_races(), with the same event and timestamps,
+// and return a JSON object with the current ratings of everyone in them.
+// Returns null on error. Free the result with randorank_string_free().
+//
+// # Safety
+//
+// The same as randorank_add_races().
+char *randorank_update(struct RandorankPeriod *period,
+                       const char *races,
+                       const char *event,
+                       const char *timestamps);
+
+// Close the period in place and start the next one with no races. Returns 0,
+// or -1 on error.
+//
+// # Safety
+//
+// period must be a live period.
+int randorank_next_period(struct RandorankPeriod *period);
+
+// The message of the last error on this thread, or null if there hasn't been
+// one. The string belongs to the library and is valid until the next call
+// that fails.
+const char *randorank_last_error(void);
+
+// Free a string returned by this library. Passing null does nothing.
+//
+// # Safety
+//
+// s must be null or a string from this library that hasn't been freed.
+void randorank_string_free(char *s);
+
+#endif /* RANDORANK_H */
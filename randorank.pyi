@@ -0,0 +1,250 @@
+from typing import Any, Dict, List, Mapping, Optional, Sequence, Tuple, Union, overload
+
+from typing_extensions import Literal, TypedDict
+
+Event = Literal['casual', 'weekly', 'qualifier', 'tournament']
+# a dict of names to times in seconds, with NaN for forfeits
+Race = Mapping[str, float]
+# a DataFrame, Arrow table or dict of columns in long format with race_id,
+# player and time columns and optionally timestamp
+RaceTable = Any
+# anything with the buffer protocol, like a NumPy array
+Array = Any
+
+class _PlayerRequired(TypedDict):
+    rating: float
+    deviation: float
+    volatility: float
+    variance: float
+    delta: float
+    inactive_periods: float
+
+class Player(_PlayerRequired, total=False):
+    races: float
+    provisional: float
+    score: float
+    rated_at: float
+
+class _RankingRequired(_PlayerRequired):
+    races: float
+    provisional: float
+    score: float
+
+# rated_at is only there with continuous rating periods
+class Ranking(_RankingRequired, total=False):
+    rated_at: float
+
+class LeaderboardEntry(Ranking):
+    position: float
+    tied: float
+    percentile: float
+
+class Convergence(TypedDict):
+    bracketing_iterations: float
+    iterations: float
+    converged: float
+    error: float
+
+class Seed(TypedDict):
+    rating: float
+    deviation: float
+
+class LedgerEntry(TypedDict):
+    seq: int
+    event: str
+    payload: str
+    previous: str
+    hash: str
+
+class Constants(TypedDict, total=False):
+    tau: float
+    multi_slope: float
+    multi_cutoff: float
+    norm_factor: float
+    victory_margin: float
+    initial_rating: float
+    initial_deviation: float
+    initial_volatility: float
+    seed_correlation: float
+    casual_weight: float
+    weekly_weight: float
+    qualifier_weight: float
+    tournament_weight: float
+    async_weight: float
+    async_norm_factor: float
+    async_window: float
+    provisional_races: float
+    provisional_deviation: float
+    provisional_periods: float
+    score_k: float
+    period_days: float
+
+class GlickoError(Exception): ...
+
+class MultiPeriod:
+    """A rating period. Add players and races, then rank them."""
+
+    def __init__(self) -> None: ...
+    @property
+    def constants(self) -> Constants: ...
+    @property
+    def players(self) -> List[str]: ...
+    @property
+    def races(self) -> Dict[int, Dict[str, float]]: ...
+    @property
+    def aliases(self) -> Dict[str, str]: ...
+    @property
+    def ledger(self) -> List[LedgerEntry]: ...
+    @staticmethod
+    def from_preset(name: str) -> MultiPeriod: ...
+    @staticmethod
+    def from_config(path: str) -> MultiPeriod: ...
+    @staticmethod
+    def from_ledger(path: str) -> MultiPeriod: ...
+    def save_config(self, path: str) -> None: ...
+    def save_ledger(self, path: str) -> None: ...
+    def set_constants(self, constants: Constants) -> None: ...
+    def set_initial_rating(self, rating: float) -> None: ...
+    def set_initial_deviation(self, deviation: float) -> None: ...
+    def set_initial_volatility(self, vol: float) -> None: ...
+    def set_glicko_tau(self, tau: float) -> None: ...
+    def set_norm_factor(self, factor: float) -> None: ...
+    def set_victory_margin(self, margin: float) -> None: ...
+    def set_multi_slope(self, slope: float) -> None: ...
+    def set_multi_cutoff(self, cutoff: float) -> None: ...
+    def set_seed_correlation(self, correlation: float) -> None: ...
+    def set_async_norm_factor(self, factor: float) -> None: ...
+    def set_async_window(self, window: float) -> None: ...
+    def set_provisional_races(self, races: float) -> None: ...
+    def set_provisional_deviation(self, deviation: float) -> None: ...
+    def set_provisional_periods(self, periods: float) -> None: ...
+    def set_score_k(self, k: float) -> None: ...
+    def set_period_days(self, days: float) -> None: ...
+    def set_event_weight(self, event: Union[Event, Literal['async']], weight: float) -> None: ...
+    def add_alias(self, alias: str, name: str) -> None: ...
+    def rename_player(self, old: str, new: str) -> None: ...
+    def merge_players(self, name: str, other: str) -> None: ...
+    def add_players(self, players: Mapping[str, Union[Player, Ranking]]) -> None: ...
+    def add_seeds(self, seeds: Mapping[str, Sequence[Seed]]) -> None: ...
+    def add_races(
+        self,
+        races: Union[Sequence[Race], Race, RaceTable],
+        event: Event = ...,
+        timestamps: Optional[Sequence[float]] = ...,
+    ) -> List[int]: ...
+    def add_race_arrays(
+        self,
+        names: Sequence[str],
+        players: Array,
+        offsets: Array,
+        times: Array,
+        event: Event = ...,
+        timestamps: Optional[Array] = ...,
+    ) -> List[int]: ...
+    def add_async_races(self, races: Sequence[Mapping[str, Tuple[float, float]]]) -> List[int]: ...
+    def remove_race(self, id: int) -> None: ...
+    def update_race(self, id: int, race: Race) -> None: ...
+    @overload
+    def rank(
+        self, end: bool = ..., at: Optional[float] = ..., arrow: Literal[False] = ...
+    ) -> Dict[str, Ranking]: ...
+    @overload
+    def rank(self, end: bool, at: Optional[float], arrow: Literal[True]) -> Any: ...
+    @overload
+    def rank(self, *, end: bool = ..., at: Optional[float] = ..., arrow: Literal[True]) -> Any: ...
+    def update(
+        self,
+        races: Sequence[Race],
+        event: Event = ...,
+        timestamps: Optional[Sequence[float]] = ...,
+    ) -> Dict[str, Ranking]: ...
+    def next_period(self) -> None: ...
+    def convergence(self) -> Dict[str, Convergence]: ...
+    def established(self, end: bool = ...) -> Dict[str, Ranking]: ...
+    def leaderboard(
+        self, end: bool = ..., provisional: bool = ...
+    ) -> List[Tuple[str, LeaderboardEntry]]: ...
+
+class League:
+    """A league stored in a SQLite database. Only built with the sqlite feature."""
+
+    def __init__(self, path: str) -> None: ...
+    @property
+    def period(self) -> int: ...
+    def set_constants(self, constants: Constants) -> None: ...
+    def add_players(self, players: Mapping[str, Union[Player, Ranking]]) -> None: ...
+    def add_races(
+        self,
+        races: Sequence[Race],
+        event: Event = ...,
+        timestamps: Optional[Sequence[float]] = ...,
+    ) -> None: ...
+    def current_period(self) -> MultiPeriod: ...
+    def close_period(self, at: Optional[float] = ...) -> Dict[str, Ranking]: ...
+    def rankings(self, period: Optional[int] = ...) -> Dict[str, Ranking]: ...
+
+class Distribution(TypedDict, total=False):
+    count: float
+    mean: float
+    stddev: float
+    skewness: float
+    kurtosis: float
+    min: float
+    max: float
+    p5: float
+    p25: float
+    p50: float
+    p75: float
+    p95: float
+    jarque_bera: float
+    jarque_bera_p: float
+    ks: float
+    ks_p: float
+
+class SimulatedPeriod(TypedDict):
+    players: float
+    races: float
+    pearson: float
+    spearman: float
+
+class Scores(TypedDict):
+    races: float
+    pairs: float
+    log_loss: Optional[float]
+    brier: Optional[float]
+    kendall_tau: Optional[float]
+    top_1: Optional[float]
+
+class SizeScores(Scores):
+    size: float
+
+class Backtest(TypedDict):
+    periods: List[Scores]
+    sizes: List[SizeScores]
+    overall: Scores
+
+def rating_interval(player: Mapping[str, float], level: float = ...) -> Tuple[float, float]: ...
+def probability_stronger(player: Mapping[str, float], opponent: Mapping[str, float]) -> float: ...
+def distinguishable(
+    player: Mapping[str, float], opponent: Mapping[str, float], level: float = ...
+) -> bool: ...
+def distribution(
+    rankings: Mapping[str, Mapping[str, float]],
+    reference: Optional[Sequence[float]] = ...,
+    key: str = ...,
+) -> Distribution: ...
+def simulate(
+    players: int = ...,
+    periods: int = ...,
+    races: int = ...,
+    min_size: int = ...,
+    max_size: int = ...,
+    forfeit_rate: float = ...,
+    seed: int = ...,
+    constants: Optional[Constants] = ...,
+) -> List[SimulatedPeriod]: ...
+def backtest(
+    periods: Sequence[Sequence[Race]],
+    players: Optional[Mapping[str, Union[Player, Ranking]]] = ...,
+    constants: Optional[Constants] = ...,
+) -> Backtest: ...
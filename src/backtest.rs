@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+#[cfg(feature = "python")]
+use pyo3::wrap_pyfunction;
+
+use crate::glicko::MultiPeriod;
+use crate::math;
+use crate::Result;
+
+// the glicko-2 scale factor, 400 / ln(10)
+const GLICKO2_SCALE: f64 = 173.7178;
+
+#[cfg(feature = "python")]
+pub fn add_functions(m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pyfunction!(backtest))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+/// Score how well ratings predict each race in a history of periods.
+///
+/// periods is a list of lists of races. Returns the scores for each
+/// period, for each race size and overall.
+#[pyfunction(players = "None", constants = "None")]
+#[text_signature = "(periods, players=None, constants=None)"]
+fn backtest(
+    py: Python,
+    periods: Vec<Vec<HashMap<String, f64>>>,
+    players: Option<HashMap<String, HashMap<String, f64>>>,
+    constants: Option<HashMap<&str, f64>>,
+) -> PyResult<PyObject> {
+    let results = run(periods, players.unwrap_or_default(), constants.as_ref())?;
+    let sizes: Vec<HashMap<&str, Option<f64>>> = results
+        .sizes
+        .iter()
+        .map(|(size, scores)| {
+            let mut size_dict = scores.to_dict();
+            size_dict.insert("size", Some(*size as f64));
+            size_dict
+        })
+        .collect();
+    let results_dict = PyDict::new(py);
+    results_dict.set_item(
+        "periods",
+        results
+            .periods
+            .iter()
+            .map(|x| x.to_dict())
+            .collect::<Vec<HashMap<&str, Option<f64>>>>(),
+    )?;
+    results_dict.set_item("sizes", sizes)?;
+    results_dict.set_item("overall", results.overall.to_dict())?;
+
+    Ok(results_dict.to_object(py))
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Scores {
+    races: u32,
+    pairs: u32,
+    log_loss: f64,
+    brier: f64,
+    tau_races: u32,
+    kendall_tau: f64,
+    top_1: f64,
+}
+
+impl Scores {
+    fn add(&mut self, other: &Scores) {
+        self.races += other.races;
+        self.pairs += other.pairs;
+        self.log_loss += other.log_loss;
+        self.brier += other.brier;
+        self.tau_races += other.tau_races;
+        self.kendall_tau += other.kendall_tau;
+        self.top_1 += other.top_1;
+    }
+
+    pub fn to_dict(&self) -> HashMap<&'static str, Option<f64>> {
+        // pair scores are averaged over every 1v1, the rest over races. with
+        // nothing to average over, a score is None rather than NaN
+        let mut scores_dict: HashMap<&str, Option<f64>> = HashMap::with_capacity(7);
+        scores_dict.insert("races", Some(self.races as f64));
+        scores_dict.insert("pairs", Some(self.pairs as f64));
+        scores_dict.insert("log_loss", average(self.log_loss, self.pairs));
+        scores_dict.insert("brier", average(self.brier, self.pairs));
+        scores_dict.insert("kendall_tau", average(self.kendall_tau, self.tau_races));
+        scores_dict.insert("top_1", average(self.top_1, self.races));
+
+        scores_dict
+    }
+}
+
+fn average(total: f64, count: u32) -> Option<f64> {
+    match count {
+        0 => None,
+        n => Some(total / n as f64),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Backtest {
+    pub periods: Vec<Scores>,
+    pub sizes: BTreeMap<usize, Scores>,
+    pub overall: Scores,
+}
+
+pub fn run(
+    periods: Vec<Vec<HashMap<String, f64>>>,
+    players: HashMap<String, HashMap<String, f64>>,
+    constants: Option<&HashMap<&str, f64>>,
+) -> Result<Backtest> {
+    // replay the history in order. every race is predicted from the ratings
+    // as they stand just before it, then added to the period
+    let mut results = Backtest::default();
+    let mut ratings = players;
+    for races in periods {
+        let mut period = MultiPeriod::default();
+        if let Some(constants) = constants {
+            period.set_constant_map(constants.clone())?;
+        }
+        period.add_player_map(ratings)?;
+        let mut period_scores = Scores::default();
+        for race in races {
+            let size = race.len();
+            let predictions: HashMap<&str, (f64, f64)> = race
+                .keys()
+                .map(|name| (name.as_str(), period.current_rating(name)))
+                .collect();
+            let race_scores = score_race(&race, &predictions);
+            period.add_race_list(vec![race], "weekly", None)?;
+            period_scores.add(&race_scores);
+            results.sizes.entry(size).or_default().add(&race_scores);
+        }
+        results.overall.add(&period_scores);
+        results.periods.push(period_scores);
+        ratings = period
+            .rankings(true)
+            .into_iter()
+            .map(|(name, player)| {
+                let player = player
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect();
+                (name.to_string(), player)
+            })
+            .collect();
+    }
+
+    Ok(results)
+}
+
+fn score_race(race: &HashMap<String, f64>, predictions: &HashMap<&str, (f64, f64)>) -> Scores {
+    let mut scores = Scores {
+        races: 1,
+        ..Scores::default()
+    };
+    let names: Vec<&str> = race.keys().map(|x| x.as_str()).collect();
+    // forfeits finish behind everyone and tie with each other
+    let finish: Vec<f64> = names
+        .iter()
+        .map(|x| match race[*x] {
+            t if t.is_nan() => f64::MIN,
+            t => -t,
+        })
+        .collect();
+    let predicted: Vec<f64> = names.iter().map(|x| predictions[x].0).collect();
+
+    for i in 0..names.len() {
+        for j in (i + 1)..names.len() {
+            if finish[i] == f64::MIN && finish[j] == f64::MIN {
+                continue;
+            }
+            let p = win_probability(predictions[names[i]], predictions[names[j]])
+                .clamp(1e-15, 1f64 - 1e-15);
+            let outcome = match finish[i] - finish[j] {
+                x if x > 0f64 => 1f64,
+                x if x < 0f64 => 0f64,
+                _ => 0.5f64,
+            };
+            scores.pairs += 1;
+            scores.log_loss -= outcome * p.ln() + (1f64 - outcome) * (1f64 - p).ln();
+            scores.brier += (p - outcome).powi(2);
+        }
+    }
+
+    let tau = math::kendall_tau(&predicted, &finish);
+    if tau.is_finite() {
+        scores.tau_races = 1;
+        scores.kendall_tau = tau;
+    }
+    // when several players share the top rating we've only picked the
+    // winner with a chance of one in however many there are
+    let best_rating = predicted.iter().cloned().fold(f64::MIN, f64::max);
+    let best_finish = finish.iter().cloned().fold(f64::MIN, f64::max);
+    let favorites: Vec<usize> = (0..names.len())
+        .filter(|&i| predicted[i] == best_rating)
+        .collect();
+    let correct = favorites
+        .iter()
+        .filter(|&&i| finish[i] == best_finish)
+        .count();
+    scores.top_1 = correct as f64 / favorites.len() as f64;
+
+    scores
+}
+
+fn win_probability(player: (f64, f64), opponent: (f64, f64)) -> f64 {
+    // the glicko-2 expected score, with the uncertainty in both ratings
+    // shrinking the difference between them
+    let diff = (player.0 - opponent.0) / GLICKO2_SCALE;
+    let phi = (player.1.powi(2) + opponent.1.powi(2)).sqrt() / GLICKO2_SCALE;
+    let g = 1f64 / (1f64 + 3f64 * phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt();
+
+    1f64 / (1f64 + (-g * diff).exp())
+}
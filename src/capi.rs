@@ -0,0 +1,277 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::glicko::MultiPeriod;
+use crate::{json, Error, Result};
+
+// a C interface to the same rating period, for services that can't host
+// python. a period is an opaque pointer the caller frees, strings in and out
+// are utf-8 json with null for forfeits, and a failed call returns -1 or null
+// with its message in randorank_last_error(). a panic can't unwind into c,
+// so every call catches one and fails the same way. that needs the capi
+// profile, since the release profile aborts on a panic instead
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A rating period. Create one with randorank_period_new() or
+/// randorank_period_from_preset() and free it with randorank_period_free().
+pub struct RandorankPeriod {
+    period: MultiPeriod,
+}
+
+/// Create an empty period with the default constants.
+#[no_mangle]
+pub extern "C" fn randorank_period_new() -> *mut RandorankPeriod {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(RandorankPeriod {
+            period: MultiPeriod::default(),
+        }))
+    })
+}
+
+/// Create a period using one of the built-in presets, "alttpr" or "glicko2".
+/// Returns null if there's no preset with that name.
+///
+/// # Safety
+///
+/// name must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_period_from_preset(name: *const c_char) -> *mut RandorankPeriod {
+    guard(ptr::null_mut(), || {
+        match text(name).and_then(MultiPeriod::preset) {
+            Ok(period) => Box::into_raw(Box::new(RandorankPeriod { period })),
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Free a period. Passing null does nothing.
+///
+/// # Safety
+///
+/// period must be null or a period from this library that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_period_free(period: *mut RandorankPeriod) {
+    guard((), || {
+        if !period.is_null() {
+            drop(Box::from_raw(period));
+        }
+    })
+}
+
+/// Set several constants at once from a JSON object of names to values.
+/// Returns 0, or -1 on error.
+///
+/// # Safety
+///
+/// period must be a live period and constants a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_set_constants(
+    period: *mut RandorankPeriod,
+    constants: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        status(with_period(period, |p| {
+            let constants = json::constants(text(constants)?)?;
+            p.set_constant_map(constants.iter().map(|(k, v)| (k.as_str(), *v)).collect())
+        }))
+    })
+}
+
+/// Add players with ratings from an earlier period, as returned by
+/// randorank_rank(). Returns 0, or -1 on error.
+///
+/// # Safety
+///
+/// period must be a live period and players a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_add_players(
+    period: *mut RandorankPeriod,
+    players: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        status(with_period(period, |p| {
+            p.add_player_map(json::players(text(players)?)?)
+        }))
+    })
+}
+
+/// Add a JSON race or list of races, each an object of names to times in
+/// seconds with null for forfeits. event is casual, weekly, qualifier or
+/// tournament, or null for weekly. timestamps is null or a JSON list of each
+/// race's unix time. Returns 0, or -1 on error.
+///
+/// # Safety
+///
+/// period must be a live period, races a valid nul-terminated string and
+/// event and timestamps null or valid nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_add_races(
+    period: *mut RandorankPeriod,
+    races: *const c_char,
+    event: *const c_char,
+    timestamps: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        status(with_period(period, |p| {
+            let races = json::races(text(races)?)?;
+            let event = optional_text(event)?.unwrap_or("weekly");
+            let timestamps = match optional_text(timestamps)? {
+                Some(x) => json::timestamps(x)?,
+                None => None,
+            };
+            p.add_race_list(races, event, timestamps).map(|_| ())
+        }))
+    })
+}
+
+/// Rank every player and return a JSON object of names to their ratings.
+/// With end set to 0, runners who raced get mid-period values instead: their
+/// pre-period volatility and rated_at, and their variance and delta so far.
+/// Returns null on error. Free the result with randorank_string_free().
+///
+/// # Safety
+///
+/// period must be a live period.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_rank(period: *mut RandorankPeriod, end: c_int) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        string(with_period(period, |p| {
+            Ok(json::to_string(&p.rankings(end != 0)))
+        }))
+    })
+}
+
+/// Add races like randorank_add_races(), with the same event and timestamps,
+/// and return a JSON object with the current ratings of everyone in them.
+/// Returns null on error. Free the result with randorank_string_free().
+///
+/// # Safety
+///
+/// The same as randorank_add_races().
+#[no_mangle]
+pub unsafe extern "C" fn randorank_update(
+    period: *mut RandorankPeriod,
+    races: *const c_char,
+    event: *const c_char,
+    timestamps: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        string(with_period(period, |p| {
+            let races = json::races(text(races)?)?;
+            let event = optional_text(event)?.unwrap_or("weekly");
+            let timestamps = match optional_text(timestamps)? {
+                Some(x) => json::timestamps(x)?,
+                None => None,
+            };
+            Ok(json::to_string(&p.update_races(races, event, timestamps)?))
+        }))
+    })
+}
+
+/// Close the period in place and start the next one with no races. Returns 0,
+/// or -1 on error.
+///
+/// # Safety
+///
+/// period must be a live period.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_next_period(period: *mut RandorankPeriod) -> c_int {
+    guard(-1, || {
+        status(with_period(period, |p| {
+            p.close();
+            Ok(())
+        }))
+    })
+}
+
+/// The message of the last error on this thread, or null if there hasn't been
+/// one. The string belongs to the library and is valid until the next call
+/// that fails.
+#[no_mangle]
+pub extern "C" fn randorank_last_error() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |x| x.as_ptr()))
+    })
+}
+
+/// Free a string returned by this library. Passing null does nothing.
+///
+/// # Safety
+///
+/// s must be null or a string from this library that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn randorank_string_free(s: *mut c_char) {
+    guard((), || {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
+
+unsafe fn with_period<T>(
+    period: *mut RandorankPeriod,
+    f: impl FnOnce(&mut MultiPeriod) -> Result<T>,
+) -> Result<T> {
+    match period.as_mut() {
+        Some(p) => f(&mut p.period),
+        None => Err(Error::new("Period is null")),
+    }
+}
+
+unsafe fn text<'a>(s: *const c_char) -> Result<&'a str> {
+    optional_text(s)?.ok_or_else(|| Error::new("Expected a string, got null"))
+}
+
+unsafe fn optional_text<'a>(s: *const c_char) -> Result<Option<&'a str>> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(x) => Ok(Some(x)),
+        Err(_) => Err(Error::new("Strings must be UTF-8")),
+    }
+}
+
+fn status(result: Result<()>) -> c_int {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            set_error(e);
+            -1
+        }
+    }
+}
+
+fn string(result: Result<String>) -> *mut c_char {
+    // json never has a nul byte in it, since they're escaped in strings
+    match result {
+        Ok(x) => CString::new(x).map_or(ptr::null_mut(), CString::into_raw),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+fn guard<T>(failed: T, f: impl FnOnce() -> T) -> T {
+    // a panic partway through a call can leave the period half changed, but
+    // the caller still gets an error instead of undefined behavior
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        set_error(Error::new("randorank panicked"));
+        failed
+    })
+}
+
+fn set_error(e: Error) {
+    let message = CString::new(e.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|x| *x.borrow_mut() = Some(message));
+}
@@ -0,0 +1,113 @@
+#[cfg(feature = "python")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+#[cfg(feature = "python")]
+use std::fs;
+#[cfg(feature = "python")]
+use std::path::Path;
+
+use crate::{Error, Result};
+
+// built-in presets only list what they change from the default constants
+const PRESETS: [(&str, &[(&str, f64)]); 2] = [
+    // the defaults were tuned on a Link to the Past Randomizer races
+    ("alttpr", &[]),
+    // glicko-2 with the values from Glickman's paper. every race is below the
+    // multiplayer cutoff so they're all rated as 1v1s, though close finishes
+    // are still weighted by the victory margin
+    (
+        "glicko2",
+        &[
+            ("tau", 0.5),
+            ("multi_cutoff", 1_000_000f64),
+            ("initial_deviation", 350f64),
+            ("initial_volatility", 0.06),
+        ],
+    ),
+];
+
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|(name, _)| *name).collect()
+}
+
+pub fn preset(name: &str) -> Result<HashMap<String, f64>> {
+    match PRESETS.iter().find(|(x, _)| *x == name) {
+        Some((_, constants)) => Ok(constants.iter().map(|(k, v)| (k.to_string(), *v)).collect()),
+        None => Err(Error::new(format!(
+            "No preset named {}. Presets are: {}",
+            name,
+            preset_names().join(", ")
+        ))),
+    }
+}
+
+#[cfg(feature = "python")]
+pub fn load(path: &str) -> Result<HashMap<String, f64>> {
+    // a config file is a flat table of constants, the same keys as the
+    // constants dict. it can name a preset to start from with "preset" and
+    // anything it doesn't set comes from there or the defaults
+    let format = Format::of(path)?;
+    let contents = fs::read_to_string(path)?;
+    let table: BTreeMap<String, Value> = match format {
+        Format::Toml => toml::from_str(&contents).map_err(|e| config_error(path, e))?,
+        Format::Json => serde_json::from_str(&contents).map_err(|e| config_error(path, e))?,
+    };
+    let mut constants: HashMap<String, f64> = match table.get("preset") {
+        Some(Value::Name(name)) => preset(name)?,
+        Some(Value::Number(_)) => return Err(config_error(path, "preset must be a name")),
+        None => HashMap::new(),
+    };
+    for (k, v) in table.into_iter().filter(|(k, _)| k != "preset") {
+        match v {
+            Value::Number(x) => constants.insert(k, x),
+            Value::Name(_) => return Err(config_error(path, format!("{} must be a number", k))),
+        };
+    }
+
+    Ok(constants)
+}
+
+#[cfg(feature = "python")]
+pub fn save(path: &str, constants: &HashMap<&str, f64>) -> Result<()> {
+    let table: BTreeMap<&str, f64> = constants.iter().map(|(k, v)| (*k, *v)).collect();
+    let contents = match Format::of(path)? {
+        Format::Toml => toml::to_string(&table).map_err(|e| config_error(path, e))?,
+        Format::Json => serde_json::to_string_pretty(&table).map_err(|e| config_error(path, e))?,
+    };
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Number(f64),
+    Name(String),
+}
+
+#[cfg(feature = "python")]
+enum Format {
+    Toml,
+    Json,
+}
+
+#[cfg(feature = "python")]
+impl Format {
+    fn of(path: &str) -> Result<Format> {
+        match Path::new(path).extension().and_then(|x| x.to_str()) {
+            Some("toml") => Ok(Format::Toml),
+            Some("json") => Ok(Format::Json),
+            _ => Err(Error::new(format!(
+                "Config file {} must end in .toml or .json",
+                path
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+fn config_error(path: &str, e: impl std::fmt::Display) -> Error {
+    Error::new(format!("Invalid config file {}: {}", path, e))
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+use std::io;
+
+#[cfg(feature = "python")]
+use pyo3::PyErr;
+
+#[cfg(feature = "python")]
+use crate::GlickoError;
+
+// errors from the core are just their messages. the bindings turn them into
+// a GlickoError for python, a thrown string for javascript and
+// randorank_last_error() for c
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error(String);
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Error {
+        Error(message.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error(e.to_string())
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<Error> for PyErr {
+    fn from(e: Error) -> PyErr {
+        GlickoError::py_err(e.0)
+    }
+}
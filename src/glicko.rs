@@ -1,13 +1,27 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     f64::{consts::PI as pi, NAN},
+    sync::Mutex,
 };
 
-use chrono::NaiveDateTime;
-use itertools::Itertools;
+#[cfg(feature = "python")]
+use std::{cmp::Ordering::Equal, fs};
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyAny, PyDict, PyList};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::{json, Value};
 
-use crate::{math, GlickoError};
+use crate::ledger::{self, Ledger};
+#[cfg(feature = "python")]
+use crate::table;
+#[cfg(feature = "python")]
+use crate::GlickoError;
+use crate::{config, math, Error, Result};
 
 #[derive(Clone, Copy, Debug)]
 struct Constants {
@@ -19,6 +33,19 @@ struct Constants {
     initial_rating: f64,
     initial_deviation: f64,
     initial_volatility: f64,
+    seed_correlation: f64,
+    casual_weight: f64,
+    weekly_weight: f64,
+    qualifier_weight: f64,
+    tournament_weight: f64,
+    async_weight: f64,
+    async_norm_factor: f64,
+    async_window: f64,
+    provisional_races: u32,
+    provisional_deviation: f64,
+    provisional_periods: u32,
+    score_k: f64,
+    period_days: f64,
 }
 
 impl Default for Constants {
@@ -32,6 +59,140 @@ impl Default for Constants {
             initial_rating: 1500.0,
             initial_deviation: 300.0,
             initial_volatility: 0.24,
+            seed_correlation: 0.0,
+            casual_weight: 0.75,
+            weekly_weight: 1.0,
+            qualifier_weight: 1.1,
+            tournament_weight: 1.25,
+            async_weight: 0.9,
+            async_norm_factor: 1.3,
+            async_window: 0.0,
+            provisional_races: 3,
+            provisional_deviation: 200.0,
+            provisional_periods: 4,
+            score_k: 2.0,
+            period_days: 0.0,
+        }
+    }
+}
+
+impl Constants {
+    fn with(&self, constants: &HashMap<&str, f64>) -> Constants {
+        // anything not in the dict keeps its current value
+        let get = |k: &str, current: f64| *constants.get(k).unwrap_or(&current);
+
+        Constants {
+            glicko_tau: get("tau", self.glicko_tau),
+            multi_slope: get("multi_slope", self.multi_slope),
+            multi_cutoff: get("multi_cutoff", self.multi_cutoff as f64) as u32,
+            norm_factor: get("norm_factor", self.norm_factor),
+            victory_margin: get("victory_margin", self.victory_margin as f64) as u32,
+            initial_rating: get("initial_rating", self.initial_rating),
+            initial_deviation: get("initial_deviation", self.initial_deviation),
+            initial_volatility: get("initial_volatility", self.initial_volatility),
+            seed_correlation: get("seed_correlation", self.seed_correlation),
+            casual_weight: get("casual_weight", self.casual_weight),
+            weekly_weight: get("weekly_weight", self.weekly_weight),
+            qualifier_weight: get("qualifier_weight", self.qualifier_weight),
+            tournament_weight: get("tournament_weight", self.tournament_weight),
+            async_weight: get("async_weight", self.async_weight),
+            async_norm_factor: get("async_norm_factor", self.async_norm_factor),
+            async_window: get("async_window", self.async_window),
+            provisional_races: get("provisional_races", self.provisional_races as f64) as u32,
+            provisional_deviation: get("provisional_deviation", self.provisional_deviation),
+            provisional_periods: get("provisional_periods", self.provisional_periods as f64) as u32,
+            score_k: get("score_k", self.score_k),
+            period_days: get("period_days", self.period_days),
+        }
+    }
+
+    fn to_map(self) -> HashMap<&'static str, f64> {
+        let mut constants: HashMap<&str, f64> = HashMap::with_capacity(20);
+        constants.insert("tau", self.glicko_tau);
+        constants.insert("multi_slope", self.multi_slope);
+        constants.insert("multi_cutoff", self.multi_cutoff as f64);
+        constants.insert("norm_factor", self.norm_factor);
+        constants.insert("victory_margin", self.victory_margin as f64);
+        constants.insert("initial_rating", self.initial_rating);
+        constants.insert("initial_deviation", self.initial_deviation);
+        constants.insert("initial_volatility", self.initial_volatility);
+        constants.insert("seed_correlation", self.seed_correlation);
+        constants.insert("casual_weight", self.casual_weight);
+        constants.insert("weekly_weight", self.weekly_weight);
+        constants.insert("qualifier_weight", self.qualifier_weight);
+        constants.insert("tournament_weight", self.tournament_weight);
+        constants.insert("async_weight", self.async_weight);
+        constants.insert("async_norm_factor", self.async_norm_factor);
+        constants.insert("async_window", self.async_window);
+        constants.insert("provisional_races", self.provisional_races as f64);
+        constants.insert("provisional_deviation", self.provisional_deviation);
+        constants.insert("provisional_periods", self.provisional_periods as f64);
+        constants.insert("score_k", self.score_k);
+        constants.insert("period_days", self.period_days);
+
+        constants
+    }
+
+    fn event_weight(&self, event: EventType) -> f64 {
+        match event {
+            EventType::Casual => self.casual_weight,
+            EventType::Weekly => self.weekly_weight,
+            EventType::Qualifier => self.qualifier_weight,
+            EventType::Tournament => self.tournament_weight,
+            EventType::Async => self.async_weight,
+        }
+    }
+
+    fn norm_factor(&self, event: EventType) -> f64 {
+        match event {
+            EventType::Async => self.async_norm_factor,
+            _ => self.norm_factor,
+        }
+    }
+
+    #[cfg(feature = "python")]
+    fn event_weight_mut(&mut self, event: EventType) -> &mut f64 {
+        match event {
+            EventType::Casual => &mut self.casual_weight,
+            EventType::Weekly => &mut self.weekly_weight,
+            EventType::Qualifier => &mut self.qualifier_weight,
+            EventType::Tournament => &mut self.tournament_weight,
+            EventType::Async => &mut self.async_weight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EventType {
+    Casual,
+    Weekly,
+    Qualifier,
+    Tournament,
+    Async,
+}
+
+impl EventType {
+    fn from_str(event: &str) -> Result<EventType> {
+        match event {
+            "casual" => Ok(EventType::Casual),
+            "weekly" => Ok(EventType::Weekly),
+            "qualifier" => Ok(EventType::Qualifier),
+            "tournament" => Ok(EventType::Tournament),
+            "async" => Ok(EventType::Async),
+            _ => Err(Error::new(format!(
+                "Unknown event type passed to method: {}",
+                event
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            EventType::Casual => "casual",
+            EventType::Weekly => "weekly",
+            EventType::Qualifier => "qualifier",
+            EventType::Tournament => "tournament",
+            EventType::Async => "async",
         }
     }
 }
@@ -60,20 +221,24 @@ impl GlickoRating {
         }
     }
 
-    fn decay_score(&mut self, inactive_periods: u32) {
-        let decayed_score: f64 =
-            self.rating - ((self.deviation.ln().powi(2) + (inactive_periods as f64).sqrt()) / 2f64);
-        self.rating = decayed_score;
+    fn decay_score(&mut self, inactive_periods: f64, elapsed: f64) {
+        // a whole period away takes off the full amount, and with continuous
+        // periods part of one takes off that part of it
+        let decay: f64 = (self.deviation.ln().powi(2) + inactive_periods.sqrt()) / 2f64;
+        self.rating -= elapsed * decay;
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Player {
     glicko_rating: GlickoRating,
     variance: f64,
     delta: f64,
-    inactive_periods: u32,
-    races: Vec<RaceResult>,
+    inactive_periods: f64,
+    race_count: u32,
+    unrated: bool,
+    rated_at: Option<NaiveDateTime>,
+    races: Vec<u64>,
 }
 
 #[derive(Debug)]
@@ -86,111 +251,324 @@ struct Opponent {
 
 #[derive(Debug)]
 struct RaceResult {
-    datetime: Option<NaiveDateTime>,
+    event: EventType,
     race_size: u32,
     player: (f64, f64, f64), // (time, glicko_score, normed_score)
     opponent: Opponent,
 }
 
-#[pyclass]
+#[derive(Clone, Copy, Debug)]
+struct Convergence {
+    iterations: math::Iterations,
+    error: Option<math::SigmaError>,
+}
+
+#[derive(Clone, Debug)]
+struct Race {
+    event: EventType,
+    datetime: Option<NaiveDateTime>,
+    num_finishers: u32,
+    times: HashMap<String, f64>,
+    normed: BTreeMap<String, (f64, f64)>, // (time, normed score)
+}
+
+impl Race {
+    fn new(
+        event: EventType,
+        datetime: Option<NaiveDateTime>,
+        times: HashMap<String, f64>,
+        norm_factor: f64,
+    ) -> Race {
+        let num_finishers = times.values().filter(|x| !x.is_nan()).count() as u32;
+        // kept sorted so every player's 1v1s are summed in the same order
+        // each time, and ranking the same period twice gives the same result
+        let normed = math::normalize_race(&times, &norm_factor)
+            .into_iter()
+            .collect();
+
+        Race {
+            event,
+            datetime,
+            num_finishers,
+            times,
+            normed,
+        }
+    }
+
+    #[cfg(feature = "python")]
+    fn rename(&mut self, old: &str, new: &str) {
+        if let Some(time) = self.times.remove(old) {
+            self.times.insert(new.to_string(), time);
+        }
+        if let Some(normed) = self.normed.remove(old) {
+            self.normed.insert(new.to_string(), normed);
+        }
+    }
+}
+
+/// A rating period. Add players and races, then rank them.
+#[cfg_attr(feature = "python", pyclass)]
+#[cfg_attr(feature = "python", text_signature = "()")]
 pub struct MultiPeriod {
     players: HashMap<String, Player>,
+    races: BTreeMap<u64, Race>,
+    next_race_id: u64,
+    aliases: HashMap<String, String>,
+    seeds: HashMap<String, Vec<GlickoRating>>,
     constants: Constants,
+    ledger: Mutex<Ledger>,
 }
 
+impl Default for MultiPeriod {
+    fn default() -> Self {
+        MultiPeriod {
+            players: HashMap::with_capacity(100),
+            races: BTreeMap::new(),
+            next_race_id: 0,
+            aliases: HashMap::new(),
+            seeds: HashMap::new(),
+            constants: Constants::default(),
+            ledger: Mutex::new(Ledger::default()),
+        }
+    }
+}
+
+impl Clone for MultiPeriod {
+    fn clone(&self) -> Self {
+        MultiPeriod {
+            players: self.players.clone(),
+            races: self.races.clone(),
+            next_race_id: self.next_race_id,
+            aliases: self.aliases.clone(),
+            seeds: self.seeds.clone(),
+            constants: self.constants,
+            ledger: Mutex::new(self.ledger.lock().unwrap().clone()),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl MultiPeriod {
     #[new]
     fn new(obj: &PyRawObject) {
-        obj.init({
-            MultiPeriod {
-                players: HashMap::with_capacity(100),
-                constants: Constants::default(),
-            }
-        })
+        obj.init(MultiPeriod::default())
     }
 
+    /// Set several constants at once from a dict of constant names to values.
+    ///
+    /// The dict must have every required constant. Optional constants it
+    /// leaves out keep their current values.
+    #[text_signature = "($self, constants)"]
     fn set_constants(&mut self, constants: HashMap<&str, f64>) -> PyResult<()> {
-        validate_constants(&constants)?;
-        let new_constants: Constants = Constants {
-            glicko_tau: constants["tau"],
-            multi_slope: constants["multi_slope"],
-            multi_cutoff: constants["multi_cutoff"] as u32,
-            norm_factor: constants["norm_factor"],
-            victory_margin: constants["victory_margin"] as u32,
-            initial_rating: constants["initial_rating"],
-            initial_deviation: constants["initial_deviation"],
-            initial_volatility: constants["initial_volatility"],
-        };
-        self.constants = new_constants;
+        Ok(self.set_constant_map(constants)?)
+    }
 
-        Ok(())
+    /// Create a period using one of the built-in presets, "alttpr" or "glicko2".
+    #[text_signature = "(name)"]
+    #[staticmethod]
+    fn from_preset(py: Python, name: &str) -> PyResult<Py<MultiPeriod>> {
+        Py::new(py, MultiPeriod::preset(name)?)
+    }
+
+    /// Create a period with constants from a .toml or .json config file.
+    #[text_signature = "(path)"]
+    #[staticmethod]
+    fn from_config(py: Python, path: &str) -> PyResult<Py<MultiPeriod>> {
+        let mut period = MultiPeriod::default();
+        period.load_constants(config::load(path)?)?;
+
+        Py::new(py, period)
+    }
+
+    /// Save the period's constants to a .toml or .json config file.
+    #[text_signature = "($self, path)"]
+    fn save_config(&self, path: &str) -> PyResult<()> {
+        Ok(config::save(path, &self.constants.to_map())?)
     }
 
+    /// Set the rating new players start with.
+    #[text_signature = "($self, rating)"]
     fn set_initial_rating(&mut self, rating: f64) -> PyResult<()> {
+        validate_constant("initial_rating", rating)?;
         self.constants.initial_rating = rating;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the deviation new players start with.
+    #[text_signature = "($self, deviation)"]
     fn set_initial_deviation(&mut self, deviation: f64) -> PyResult<()> {
+        validate_constant("initial_deviation", deviation)?;
         self.constants.initial_deviation = deviation;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the volatility new players start with.
+    #[text_signature = "($self, vol)"]
     fn set_initial_volatility(&mut self, vol: f64) -> PyResult<()> {
+        validate_constant("initial_volatility", vol)?;
         self.constants.initial_volatility = vol;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set tau, the Glicko system constant.
+    #[text_signature = "($self, tau)"]
     fn set_glicko_tau(&mut self, tau: f64) -> PyResult<()> {
+        validate_constant("tau", tau)?;
         self.constants.glicko_tau = tau;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the normalization factor that decides a race's floor time.
+    #[text_signature = "($self, factor)"]
     fn set_norm_factor(&mut self, factor: f64) -> PyResult<()> {
+        validate_constant("norm_factor", factor)?;
         self.constants.norm_factor = factor;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the time in seconds that gives a 1v1 full weight in races below
+    /// the cutoff.
+    #[text_signature = "($self, margin)"]
     fn set_victory_margin(&mut self, margin: f64) -> PyResult<()> {
+        validate_constant("victory_margin", margin)?;
         self.constants.victory_margin = margin as u32;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the slope used to weigh 1v1s in races above the cutoff.
+    #[text_signature = "($self, slope)"]
     fn set_multi_slope(&mut self, slope: f64) -> PyResult<()> {
+        validate_constant("multi_slope", slope)?;
         self.constants.multi_slope = slope;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set how many runners a race needs to use the multiplayer implementation.
+    #[text_signature = "($self, cutoff)"]
     fn set_multi_cutoff(&mut self, cutoff: f64) -> PyResult<()> {
+        validate_constant("multi_cutoff", cutoff)?;
         self.constants.multi_cutoff = cutoff as u32;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set how well skill in other categories carries over to seeds, from 0 to 1.
+    #[text_signature = "($self, correlation)"]
+    fn set_seed_correlation(&mut self, correlation: f64) -> PyResult<()> {
+        validate_constant("seed_correlation", correlation)?;
+        self.constants.seed_correlation = correlation;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set the norm factor used for async races.
+    #[text_signature = "($self, factor)"]
+    fn set_async_norm_factor(&mut self, factor: f64) -> PyResult<()> {
+        validate_constant("async_norm_factor", factor)?;
+        self.constants.async_norm_factor = factor;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set how long in seconds an async stays open after its first
+    /// submission, or 0 for no limit.
+    #[text_signature = "($self, window)"]
+    fn set_async_window(&mut self, window: f64) -> PyResult<()> {
+        validate_constant("async_window", window)?;
+        self.constants.async_window = window;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set the number of races a player needs to be established.
+    #[text_signature = "($self, races)"]
+    fn set_provisional_races(&mut self, races: f64) -> PyResult<()> {
+        validate_constant("provisional_races", races)?;
+        self.constants.provisional_races = races as u32;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set the highest deviation an established player can have.
+    #[text_signature = "($self, deviation)"]
+    fn set_provisional_deviation(&mut self, deviation: f64) -> PyResult<()> {
+        validate_constant("provisional_deviation", deviation)?;
+        self.constants.provisional_deviation = deviation;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set how many periods an established player can be inactive.
+    #[text_signature = "($self, periods)"]
+    fn set_provisional_periods(&mut self, periods: f64) -> PyResult<()> {
+        validate_constant("provisional_periods", periods)?;
+        self.constants.provisional_periods = periods as u32;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set how many deviations are taken off a player's rating for their
+    /// score.
+    #[text_signature = "($self, k)"]
+    fn set_score_k(&mut self, k: f64) -> PyResult<()> {
+        validate_constant("score_k", k)?;
+        self.constants.score_k = k;
+        self.record_constants();
 
         Ok(())
     }
 
+    /// Set the length of a rating period in days, or 0 to count whole
+    /// periods.
+    #[text_signature = "($self, days)"]
+    fn set_period_days(&mut self, days: f64) -> PyResult<()> {
+        validate_constant("period_days", days)?;
+        self.constants.period_days = days;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Set how much 1v1s from one event type count towards the period.
+    #[text_signature = "($self, event, weight)"]
+    fn set_event_weight(&mut self, event: &str, weight: f64) -> PyResult<()> {
+        validate_event_weight(weight)?;
+        *self.constants.event_weight_mut(EventType::from_str(event)?) = weight;
+        self.record_constants();
+
+        Ok(())
+    }
+
+    /// Every constant as a dict of names to values.
     #[cfg_attr(rustfmt, rustfmt_skip)]
     #[getter]
     fn get_constants(&self) -> PyResult<HashMap<&str, f64>> {
-        let mut constants: HashMap<&str, f64> = HashMap::with_capacity(6);
-        constants.insert("tau", self.constants.glicko_tau);
-        constants.insert("multi_slope", self.constants.multi_slope);
-        constants.insert("multi_cutoff", self.constants.multi_cutoff as f64);
-        constants.insert("norm_factor", self.constants.norm_factor);
-        constants.insert("victory_margin", self.constants.victory_margin as f64);
-        constants.insert("initial_rating", self.constants.initial_rating);
-        constants.insert("initial_deviation", self.constants.initial_deviation);
-        constants.insert("initial_volatility", self.constants.initial_volatility,);
-
-        Ok(constants)
+        Ok(self.constants.to_map())
     }
 
+    /// The names of every player in the period.
     #[getter]
     fn get_players(&self) -> Vec<&String> {
         let players: Vec<&String> = self.players.keys().collect();
@@ -198,9 +576,440 @@ impl MultiPeriod {
         players
     }
 
+    /// Every race in the period by ID, as dicts of names to times.
+    #[getter]
+    fn get_races(&self) -> HashMap<u64, HashMap<String, f64>> {
+        let races: HashMap<u64, HashMap<String, f64>> = self
+            .races
+            .iter()
+            .map(|(id, race)| (*id, race.times.clone()))
+            .collect();
+
+        races
+    }
+
+    /// Every alias and the name it points to.
+    #[getter]
+    fn get_aliases(&self) -> HashMap<String, String> {
+        self.aliases.clone()
+    }
+
+    /// Treat alias as another name for a player in every race added after this.
+    #[text_signature = "($self, alias, name)"]
+    fn add_alias(&mut self, alias: &str, name: &str) -> PyResult<()> {
+        let name = self.resolve(name);
+        if alias == name || self.players.contains_key(alias) {
+            return Err(GlickoError::py_err(format!(
+                "Cannot alias existing player {}, merge them instead",
+                alias
+            )));
+        }
+        if let Some(target) = self.aliases.get(alias).filter(|&x| *x != name) {
+            return Err(GlickoError::py_err(format!(
+                "{} is already an alias for {}",
+                alias, target
+            )));
+        }
+        self.record("add_alias", json!({ "alias": alias, "name": name }));
+        self.aliases.insert(alias.to_string(), name);
+
+        Ok(())
+    }
+
+    /// Rename a player everywhere in the period.
+    #[text_signature = "($self, old, new)"]
+    fn rename_player(&mut self, old: &str, new: &str) -> PyResult<()> {
+        // a player can take back a name they had before, which is only an
+        // alias for them now
+        let own_alias = self.aliases.get(new).is_some_and(|x| x == old);
+        if self.players.contains_key(new) || (self.aliases.contains_key(new) && !own_alias) {
+            return Err(GlickoError::py_err(format!(
+                "Player {} already exists",
+                new
+            )));
+        }
+        let player = match self.players.remove(old) {
+            Some(p) => p,
+            None => return Err(GlickoError::py_err(format!("No player named {}", old))),
+        };
+        self.aliases.remove(new);
+        // a player only refers to races by id, so only the stored races
+        // need to know about the new name
+        self.players.insert(new.to_string(), player);
+        for race in self.races.values_mut() {
+            race.rename(old, new);
+        }
+        if let Some(seeds) = self.seeds.remove(old) {
+            self.seeds.insert(new.to_string(), seeds);
+        }
+        self.point_aliases(old, new);
+
+        self.record("rename_player", json!({ "old": old, "new": new }));
+
+        Ok(())
+    }
+
+    /// Merge other into name, combining their races and keeping whichever of
+    /// their ratings has the lower deviation.
+    #[text_signature = "($self, name, other)"]
+    fn merge_players(&mut self, name: &str, other: &str) -> PyResult<()> {
+        if name == other || !self.players.contains_key(name) || !self.players.contains_key(other) {
+            return Err(GlickoError::py_err(format!(
+                "Cannot merge {} into {}",
+                other, name
+            )));
+        }
+        let ids: Vec<u64> = self
+            .races
+            .iter()
+            .filter(|(_, r)| r.times.contains_key(name) || r.times.contains_key(other))
+            .map(|(id, _)| *id)
+            .collect();
+        if ids.iter().any(|id| {
+            self.races[id].times.contains_key(name) && self.races[id].times.contains_key(other)
+        }) {
+            return Err(GlickoError::py_err(format!(
+                "Cannot merge {} into {}: They were in the same race",
+                other, name
+            )));
+        }
+
+        // take every race either account was in out of the period, then put
+        // them back under the merged player so everyone's 1v1s against them
+        // are recomputed with the merged rating
+        let player = self.players.remove(name).unwrap();
+        let other_player = self.players.remove(other).unwrap();
+        let mut races: Vec<(u64, Race)> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let mut race = self.races.remove(&id).unwrap();
+            self.unindex_race(id, &race.times);
+            race.rename(other, name);
+            races.push((id, race));
+        }
+        self.players
+            .insert(name.to_string(), merge(player, other_player));
+        for (id, race) in races {
+            self.index_race(id, race)?;
+        }
+        if let Some(seeds) = self.seeds.remove(other) {
+            self.seeds
+                .entry(name.to_string())
+                .or_default()
+                .extend(seeds);
+        }
+        self.point_aliases(other, name);
+
+        self.record("merge_players", json!({ "name": name, "other": other }));
+
+        Ok(())
+    }
+
+    /// Add players with ratings from an earlier period.
+    ///
+    /// players is a dict of names to the player dicts returned by rank().
+    #[text_signature = "($self, players)"]
     fn add_players(&mut self, players: HashMap<String, HashMap<String, f64>>) -> PyResult<()> {
+        Ok(self.add_player_map(players)?)
+    }
+
+    /// Seed new players from their ratings in other categories.
+    ///
+    /// seeds is a dict of names to lists of dicts with a rating and deviation.
+    #[text_signature = "($self, seeds)"]
+    fn add_seeds(&mut self, seeds: HashMap<String, Vec<HashMap<String, f64>>>) -> PyResult<()> {
+        validate_seeds(&seeds)?;
+        for (name, ratings) in seeds.iter() {
+            let entry = self.seeds.entry(self.resolve(name)).or_default();
+            for r in ratings.iter() {
+                entry.push(GlickoRating {
+                    rating: r["rating"],
+                    deviation: r["deviation"],
+                    volatility: self.constants.initial_volatility,
+                });
+            }
+        }
+
+        let seeds: BTreeMap<&String, Vec<Value>> = seeds
+            .iter()
+            .map(|(k, v)| (k, v.iter().map(ledger::numbers).collect()))
+            .collect();
+        self.record("add_seeds", json!({ "seeds": seeds }));
+
+        Ok(())
+    }
+
+    /// Add races to the period and return their IDs.
+    ///
+    /// races is a list of dicts of names to times in seconds, with NaN for
+    /// forfeits, a single one of those dicts, or a table in long format with
+    /// race_id, player and time columns. event is casual, weekly, qualifier or tournament, and
+    /// timestamps optionally gives each race's unix time.
+    #[text_signature = "($self, races, event='weekly', timestamps=None)"]
+    #[args(event = "\"weekly\"", timestamps = "None")]
+    fn add_races(
+        &mut self,
+        races: &PyAny,
+        event: &str,
+        timestamps: Option<Vec<f64>>,
+    ) -> PyResult<Vec<u64>> {
+        // races can be a list of dicts, a single race or a table of them in
+        // long format
+        match table::columns(races)? {
+            Some(table) => {
+                let (races, table_timestamps) = table::races(&table)?;
+                Ok(self.add_race_list(races, event, timestamps.or(table_timestamps))?)
+            }
+            None if races.downcast_ref::<PyDict>().is_ok() => {
+                Ok(self.add_race_list(vec![races.extract()?], event, timestamps)?)
+            }
+            None => Ok(self.add_race_list(races.extract()?, event, timestamps)?),
+        }
+    }
+
+    /// Add races from flat arrays and return their IDs.
+    ///
+    /// players holds an index into names for every row and times the matching
+    /// times. Race i is rows offsets[i] up to offsets[i + 1].
+    #[text_signature = "($self, names, players, offsets, times, event='weekly', timestamps=None)"]
+    #[args(event = "\"weekly\"", timestamps = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn add_race_arrays(
+        &mut self,
+        py: Python,
+        names: Vec<String>,
+        players: &PyAny,
+        offsets: &PyAny,
+        times: &PyAny,
+        event: &str,
+        timestamps: Option<&PyAny>,
+    ) -> PyResult<Vec<u64>> {
+        // the bulk version of add_races, reading numpy arrays (or anything
+        // else with the buffer protocol) without a dict for every race
+        let races = table::race_arrays(py, &names, players, offsets, times)?;
+        let timestamps = match timestamps {
+            Some(x) => Some(table::floats(py, x)?),
+            None => None,
+        };
+
+        Ok(self.add_race_list(races, event, timestamps)?)
+    }
+
+    /// Add async races and return their IDs.
+    ///
+    /// Each race is a dict of names to (time, unix time of submission) tuples.
+    #[text_signature = "($self, races)"]
+    fn add_async_races(&mut self, races: Vec<HashMap<String, (f64, f64)>>) -> PyResult<Vec<u64>> {
+        let mut ids: Vec<u64> = Vec::with_capacity(races.len());
+        for race in races.iter() {
+            let (times, opened) = validate_async_race(race, self.constants.async_window)?;
+            validate_race(&times)?;
+            ids.push(self.add_race(&times, EventType::Async, from_timestamp(opened))?);
+            let race: BTreeMap<&String, Value> = race
+                .iter()
+                .map(|(k, v)| (k, json!([ledger::number(v.0), ledger::number(v.1)])))
+                .collect();
+            self.record("add_async_races", json!({ "race": race }));
+        }
+
+        Ok(ids)
+    }
+
+    /// Remove a race from the period.
+    #[text_signature = "($self, id)"]
+    fn remove_race(&mut self, id: u64) -> PyResult<()> {
+        let race = match self.races.remove(&id) {
+            Some(r) => r,
+            None => return Err(GlickoError::py_err(format!("No race with id {}", id))),
+        };
+        self.unindex_race(id, &race.times);
+
+        self.record("remove_race", json!({ "id": id }));
+
+        Ok(())
+    }
+
+    /// Replace a race's times.
+    #[text_signature = "($self, id, race)"]
+    fn update_race(&mut self, id: u64, race: HashMap<String, f64>) -> PyResult<()> {
+        // check the new times before touching the old race, so a bad update
+        // leaves the period as it was
+        validate_race(&race)?;
+        let times = self.resolve_race(&race)?;
+        let old_race = match self.races.remove(&id) {
+            Some(r) => r,
+            None => return Err(GlickoError::py_err(format!("No race with id {}", id))),
+        };
+        self.unindex_race(id, &old_race.times);
+        let new_race = Race::new(
+            old_race.event,
+            old_race.datetime,
+            times,
+            self.constants.norm_factor(old_race.event),
+        );
+        self.index_race(id, new_race)?;
+
+        self.record(
+            "update_race",
+            json!({ "id": id, "race": ledger::numbers(&race) }),
+        );
+
+        Ok(())
+    }
+
+    /// Rank every player and return a dict of names to player dicts.
+    ///
+    /// Each player dict has their rating, deviation, volatility, variance,
+    /// delta, inactive_periods, races, provisional and score, and rated_at with
+    /// continuous rating periods. end=False returns mid-period values for
+    /// runners who raced: their pre-period volatility and rated_at, and their
+    /// variance and delta so far instead of zero. at is the unix time to rate
+    /// everyone as of, and arrow=True returns a pyarrow RecordBatch instead.
+    #[text_signature = "($self, end=True, at=None, arrow=False)"]
+    #[args(end = true, at = "None", arrow = false)]
+    fn rank(&self, py: Python, end: bool, at: Option<f64>, arrow: bool) -> PyResult<PyObject> {
+        let rankings_dict = self.ranked(py, end, at)?;
+        match arrow {
+            true => table::record_batch(py, &rankings_dict),
+            false => Ok(rankings_dict.to_object(py)),
+        }
+    }
+
+    /// Add races and return the current ratings of everyone in them.
+    ///
+    /// The period isn't closed, so every race is still only counted once.
+    #[text_signature = "($self, races, event='weekly', timestamps=None)"]
+    #[args(event = "\"weekly\"", timestamps = "None")]
+    fn update(
+        &mut self,
+        races: Vec<HashMap<String, f64>>,
+        event: &str,
+        timestamps: Option<Vec<f64>>,
+    ) -> PyResult<HashMap<String, HashMap<String, f64>>> {
+        Ok(self.update_races(races, event, timestamps)?)
+    }
+
+    /// Close the period in place and start the next one with no races.
+    #[text_signature = "($self)"]
+    fn next_period(&mut self) -> PyResult<()> {
+        self.close();
+
+        Ok(())
+    }
+
+    /// Every entry in the period's ledger as a list of dicts with seq, event,
+    /// payload, previous and hash.
+    #[getter]
+    fn get_ledger(&self, py: Python) -> PyResult<PyObject> {
+        let ledger = self.ledger.lock().unwrap();
+        let entries = PyList::empty(py);
+        for (seq, entry) in ledger.entries().iter().enumerate() {
+            let entry_dict = PyDict::new(py);
+            entry_dict.set_item("seq", seq)?;
+            entry_dict.set_item("event", &entry.event)?;
+            entry_dict.set_item("payload", entry.payload.to_string())?;
+            entry_dict.set_item("previous", &entry.previous)?;
+            entry_dict.set_item("hash", &entry.hash)?;
+            entries.append(entry_dict)?;
+        }
+
+        Ok(entries.to_object(py))
+    }
+
+    /// Save the ledger as JSON lines.
+    #[text_signature = "($self, path)"]
+    fn save_ledger(&self, path: &str) -> PyResult<()> {
+        fs::write(path, self.ledger.lock().unwrap().to_lines())?;
+
+        Ok(())
+    }
+
+    /// Rebuild a period from a saved ledger, checking every entry's hash.
+    #[text_signature = "(path)"]
+    #[staticmethod]
+    fn from_ledger(py: Python, path: &str) -> PyResult<Py<MultiPeriod>> {
+        let entries = ledger::parse(&fs::read_to_string(path)?)?;
+
+        Py::new(py, MultiPeriod::replay(&entries)?)
+    }
+
+    /// Report whether each player's new volatility converged.
+    ///
+    /// Each player's dict has the bracketing_iterations and iterations each
+    /// step of the search needed, converged (1 or 0) and an error code, 0 when
+    /// there wasn't one.
+    #[text_signature = "($self)"]
+    fn convergence(&self) -> PyResult<HashMap<&str, HashMap<&'static str, f64>>> {
+        Ok(self.convergence_map())
+    }
+
+    /// Like rank(), but only the players who are no longer provisional.
+    #[text_signature = "($self, end=True)"]
+    #[args(end = true)]
+    fn established(
+        &self,
+        py: Python,
+        end: bool,
+    ) -> PyResult<HashMap<String, HashMap<&'static str, f64>>> {
+        let mut rankings_dict = self.ranked(py, end, None)?;
+        rankings_dict.retain(|_, v| v["provisional"] == 0f64);
+
+        Ok(rankings_dict)
+    }
+
+    /// Players sorted by score as a list of (name, dict) tuples.
+    ///
+    /// Each dict adds the player's position, whether they're tied and their
+    /// percentile. Provisional players are left off unless provisional=True.
+    #[text_signature = "($self, end=True, provisional=False)"]
+    #[args(end = true, provisional = false)]
+    fn leaderboard(
+        &self,
+        py: Python,
+        end: bool,
+        provisional: bool,
+    ) -> PyResult<Vec<(String, HashMap<&'static str, f64>)>> {
+        let rankings_dict = if provisional {
+            self.ranked(py, end, None)?
+        } else {
+            self.established(py, end)?
+        };
+        let mut board: Vec<(String, HashMap<&str, f64>)> = rankings_dict.into_iter().collect();
+        board.sort_by(|a, b| {
+            b.1["score"]
+                .partial_cmp(&a.1["score"])
+                .unwrap_or(Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        place(&mut board);
+
+        Ok(board)
+    }
+}
+
+// methods not exposed to python directly. the pub ones are the rust side of
+// the python methods, which the wasm and c interfaces call too
+impl MultiPeriod {
+    pub fn preset(name: &str) -> Result<MultiPeriod> {
+        let mut period = MultiPeriod::default();
+        period.load_constants(config::preset(name)?)?;
+
+        Ok(period)
+    }
+
+    pub fn set_constant_map(&mut self, constants: HashMap<&str, f64>) -> Result<()> {
+        // the rust side of set_constants()
+        validate_constants(&constants)?;
+        self.constants = self.constants.with(&constants);
+        self.record_constants();
+
+        Ok(())
+    }
+
+    pub fn add_player_map(&mut self, players: HashMap<String, HashMap<String, f64>>) -> Result<()> {
+        // the rust side of add_players()
         validate_players(&players)?;
         for p in players.keys() {
+            let name = self.resolve(p);
             let glicko = GlickoRating {
                 rating: players[p]["rating"],
                 deviation: players[p]["deviation"],
@@ -210,73 +1019,477 @@ impl MultiPeriod {
                 glicko_rating: glicko,
                 variance: players[p]["variance"],
                 delta: players[p]["delta"],
-                inactive_periods: players[p]["inactive_periods"] as u32,
-                races: Vec::with_capacity(20),
+                inactive_periods: players[p]["inactive_periods"],
+                race_count: *players[p].get("races").unwrap_or(&0f64) as u32,
+                unrated: false,
+                rated_at: players[p].get("rated_at").and_then(|x| from_timestamp(*x)),
+                // keep any races they've already been entered in this period
+                races: self
+                    .players
+                    .remove(&name)
+                    .map_or_else(|| Vec::with_capacity(20), |x| x.races),
             };
 
-            self.players.insert(p.to_string(), player);
+            self.players.insert(name, player);
         }
 
+        self.record(
+            "add_players",
+            json!({ "players": ledger::nested(&players) }),
+        );
+
         Ok(())
     }
 
-    fn add_races(&mut self, races: Vec<HashMap<String, f64>>) -> PyResult<()> {
+    pub fn update_races(
+        &mut self,
+        races: Vec<HashMap<String, f64>>,
+        event: &str,
+        timestamps: Option<Vec<f64>>,
+    ) -> Result<HashMap<String, HashMap<String, f64>>> {
+        // the rust side of update(). add races to the period as they happen
+        // and get the current ratings of everyone in them. these are what
+        // the ratings would be if the period ended now, but nothing is
+        // closed. the period keeps its starting ratings and every race, so
+        // each race is only counted once
         for race in races.iter() {
-            validate_race(&race)?;
-            self.add_new_players(&race)?;
-            let num_finishers = race
-                .values()
-                .filter(|x| x.is_nan() == false)
-                .map(|x| *x)
-                .collect::<Vec<f64>>()
-                .len();
-            let normed_race = math::normalize_race(&race, &self.constants.norm_factor);
-            self.make_pairings(&normed_race, num_finishers)?;
+            validate_race(race)?;
         }
+        let ids = self.add_race_list(races, event, timestamps)?;
+        let mut names: Vec<String> = ids
+            .iter()
+            .flat_map(|id| self.races[id].times.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        let at = self.latest_race();
+        let current = names
+            .into_iter()
+            .map(|name| {
+                let player_dict = self
+                    .rank_player(&name, &self.players[&name], true, at)
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect();
+                (name, player_dict)
+            })
+            .collect();
 
-        Ok(())
+        Ok(current)
     }
 
-    #[args(end = true)]
-    fn rank(&self, end: bool) -> PyResult<HashMap<&str, HashMap<&str, f64>>> {
-        let mut rankings_dict: HashMap<&str, HashMap<&str, f64>> =
+    pub fn close(&mut self) {
+        // the rust side of next_period(). close the period in place.
+        // everyone's end of period rating becomes where they start the next
+        // one and the period's races are cleared, the same as passing rank()
+        // to add_players() of a new period
+        let rankings: Vec<(String, HashMap<String, f64>)> = self
+            .rankings(true)
+            .into_iter()
+            .map(|(name, player_dict)| {
+                let player_dict = player_dict
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect();
+                (name.to_string(), player_dict)
+            })
+            .collect();
+        for (name, r) in rankings {
+            let player = self.players.get_mut(&name).unwrap();
+            player.glicko_rating = GlickoRating {
+                rating: r["rating"],
+                deviation: r["deviation"],
+                volatility: r["volatility"],
+            };
+            player.variance = 0f64;
+            player.delta = 0f64;
+            player.inactive_periods = r["inactive_periods"];
+            player.race_count = r["races"] as u32;
+            player.unrated = false;
+            player.rated_at = r.get("rated_at").and_then(|x| from_timestamp(*x));
+            player.races.clear();
+        }
+        self.races.clear();
+
+        self.record("next_period", json!({}));
+    }
+
+    pub fn add_race_list(
+        &mut self,
+        races: Vec<HashMap<String, f64>>,
+        event: &str,
+        timestamps: Option<Vec<f64>>,
+    ) -> Result<Vec<u64>> {
+        // the rust side of add_races(), for a list of races
+        let event = EventType::from_str(event)?;
+        if event == EventType::Async {
+            return Err(Error::new("Async races must be added with add_async_races"));
+        }
+        let datetimes = validate_timestamps(timestamps, races.len())?;
+        let mut ids: Vec<u64> = Vec::with_capacity(races.len());
+        for (race, datetime) in races.iter().zip(datetimes) {
+            validate_race(race)?;
+            ids.push(self.add_race(race, event, datetime)?);
+            // each race is its own entry so the ledger matches the period
+            // even if a later race in the list is rejected
+            let timestamp = datetime.map_or(Value::Null, |x| ledger::number(to_timestamp(x)));
+            self.record(
+                "add_races",
+                json!({
+                    "race": ledger::numbers(race),
+                    "event": event.name(),
+                    "timestamp": timestamp,
+                }),
+            );
+        }
+
+        Ok(ids)
+    }
+
+    // the rust side of rank(), which doesn't need the gil
+    pub fn rankings(&self, end: bool) -> HashMap<&str, HashMap<&'static str, f64>> {
+        self.rankings_at(end, self.latest_race())
+    }
+
+    pub fn rankings_at(
+        &self,
+        end: bool,
+        at: Option<NaiveDateTime>,
+    ) -> HashMap<&str, HashMap<&'static str, f64>> {
+        // each player's update only reads the period, so with the rayon
+        // feature we can compute them all in parallel
+        #[cfg(feature = "rayon")]
+        let players = self.players.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let players = self.players.iter();
+
+        players
+            .map(|(name, player)| (name.as_str(), self.rank_player(name, player, end, at)))
+            .collect()
+    }
+
+    pub fn convergence_map(&self) -> HashMap<&str, HashMap<&'static str, f64>> {
+        // the rust side of convergence(). diagnostics for the volatility
+        // iteration of every player who raced. a player who didn't converge
+        // keeps their previous volatility
+        let mut convergence_dict: HashMap<&str, HashMap<&'static str, f64>> =
             HashMap::with_capacity(self.players.len());
-        for (name, player) in self.players.iter() {
-            if player.races.len() == 0 {
-                // player hasn't raced. only change RD and apply
-                let player_dict = self.process_inactive(player);
-                rankings_dict.insert(name, player_dict);
-            } else {
-                // player has raced, process their 1v1s and add them to the
-                // rankings hash map
-                let player_dict = self.process_1v1s(player, end);
-                rankings_dict.insert(name, player_dict);
+        for (name, player) in self.players.iter().filter(|(_, p)| !p.races.is_empty()) {
+            let (_, convergence) = self.process_1v1s(name, player, true, self.latest_race());
+            let mut player_dict: HashMap<&str, f64> = HashMap::with_capacity(4);
+            player_dict.insert(
+                "bracketing_iterations",
+                convergence.iterations.bracketing as f64,
+            );
+            player_dict.insert("iterations", convergence.iterations.convergence as f64);
+            player_dict.insert(
+                "converged",
+                if convergence.error.is_none() {
+                    1f64
+                } else {
+                    0f64
+                },
+            );
+            player_dict.insert("error", convergence.error.map_or(0f64, |e| e.code()));
+            convergence_dict.insert(name, player_dict);
+        }
+
+        convergence_dict
+    }
+
+    fn latest_race(&self) -> Option<NaiveDateTime> {
+        self.races.values().filter_map(|r| r.datetime).max()
+    }
+
+    fn elapsed_periods(
+        &self,
+        player: &Player,
+        at: Option<NaiveDateTime>,
+    ) -> (f64, Option<NaiveDateTime>) {
+        // returns how many rating periods the player's deviation should grow
+        // by and the time their new rating is as of. with a period length
+        // set, that's the time since they were last rated. otherwise, or
+        // without the dates to measure it, it's one whole period
+        let last_race = player
+            .races
+            .iter()
+            .filter_map(|id| self.races[id].datetime)
+            .max();
+        let until = match (last_race, at) {
+            (Some(r), Some(a)) => Some(r.max(a)),
+            (r, a) => r.or(a),
+        };
+        match (until, player.rated_at) {
+            (Some(until), Some(rated_at)) if self.constants.period_days > 0f64 => {
+                let days = (until - rated_at).num_seconds() as f64 / 86400f64;
+                ((days / self.constants.period_days).max(0f64), Some(until))
             }
+            _ => (1f64, until),
         }
+    }
+
+    pub fn num_pairings(&self) -> usize {
+        // how many 1v1s the period's races pair up into
+        self.players
+            .iter()
+            .map(|(name, player)| self.make_pairings(name, player).count())
+            .sum()
+    }
+
+    #[cfg(feature = "python")]
+    fn ranked(
+        &self,
+        py: Python,
+        end: bool,
+        at: Option<f64>,
+    ) -> Result<HashMap<String, HashMap<&'static str, f64>>> {
+        let at = self.rank_time(at)?;
+        // other python threads can run while we work through the players, so
+        // they might change the period under us. rank a copy of it instead,
+        // made while we still hold the GIL
+        let snapshot = self.snapshot();
+        let rankings_dict = py.allow_threads(move || {
+            snapshot
+                .rankings_at(end, at)
+                .into_iter()
+                .map(|(name, x)| (name.to_string(), x))
+                .collect()
+        });
+        self.record_rank(end, at, &rankings_dict);
 
         Ok(rankings_dict)
     }
-}
 
-// private methods not accessible from python
-impl MultiPeriod {
+    #[cfg(feature = "python")]
+    fn snapshot(&self) -> MultiPeriod {
+        // everything ranking reads. the ledger is left behind, since it's
+        // only ever appended to and can be long
+        MultiPeriod {
+            players: self.players.clone(),
+            races: self.races.clone(),
+            next_race_id: self.next_race_id,
+            aliases: self.aliases.clone(),
+            seeds: self.seeds.clone(),
+            constants: self.constants,
+            ledger: Mutex::new(Ledger::default()),
+        }
+    }
+
+    fn record(&self, event: &str, payload: Value) {
+        self.ledger.lock().unwrap().record(event, payload);
+    }
+
+    fn record_constants(&self) {
+        // any change to the constants records all of them
+        self.record(
+            "set_constants",
+            json!({ "constants": ledger::numbers(&self.constants.to_map()) }),
+        );
+    }
+
+    #[cfg(feature = "python")]
+    fn record_rank<K: AsRef<str>>(
+        &self,
+        end: bool,
+        at: Option<NaiveDateTime>,
+        rankings: &HashMap<K, HashMap<&str, f64>>,
+    ) {
+        // rankings aren't stored, only a hash of them, so a published
+        // leaderboard can be checked against the ledger
+        let at = at.map_or(Value::Null, |x| ledger::number(to_timestamp(x)));
+        let result = ledger::hash_value(&ledger::nested(rankings));
+        self.record("rank", json!({ "end": end, "at": at, "result": result }));
+    }
+
+    pub fn rank_time(&self, at: Option<f64>) -> Result<Option<NaiveDateTime>> {
+        match at {
+            Some(x) => match from_timestamp(x) {
+                Some(d) => Ok(Some(d)),
+                None => Err(Error::new(format!("Invalid timestamp {}", x))),
+            },
+            None => Ok(self.latest_race()),
+        }
+    }
+
+    #[cfg(feature = "python")]
+    fn replay(entries: &[ledger::Entry]) -> PyResult<MultiPeriod> {
+        // rebuild a period by doing everything in its ledger again. each
+        // step records the same entry as the original did, so the rebuilt
+        // ledger's hashes have to match the saved ones as we go
+        let mut period = MultiPeriod::default();
+        for (seq, entry) in entries.iter().enumerate() {
+            let payload = &entry.payload;
+            let text = |k: &str| payload[k].as_str().unwrap_or("").to_string();
+            match entry.event.as_str() {
+                "set_constants" => period.load_constants(ledger::floats(&payload["constants"])?)?,
+                "add_players" => {
+                    period.add_player_map(ledger::nested_floats(&payload["players"])?)?
+                }
+                "add_seeds" => {
+                    let seeds = match payload["seeds"].as_object() {
+                        Some(seeds) => seeds
+                            .iter()
+                            .map(|(k, v)| {
+                                let ratings = v.as_array().map_or(Ok(Vec::new()), |x| {
+                                    x.iter().map(ledger::floats).collect()
+                                })?;
+                                Ok((k.clone(), ratings))
+                            })
+                            .collect::<Result<_>>()?,
+                        None => HashMap::new(),
+                    };
+                    period.add_seeds(seeds)?
+                }
+                "add_races" => {
+                    let timestamps = payload["timestamp"].as_f64().map(|x| vec![x]);
+                    period.add_race_list(
+                        vec![ledger::floats(&payload["race"])?],
+                        &text("event"),
+                        timestamps,
+                    )?;
+                }
+                "add_async_races" => {
+                    let race = match payload["race"].as_object() {
+                        Some(race) => race
+                            .iter()
+                            .map(|(k, v)| (k.clone(), (ledger::float(&v[0]), ledger::float(&v[1]))))
+                            .collect(),
+                        None => HashMap::new(),
+                    };
+                    period.add_async_races(vec![race])?;
+                }
+                "remove_race" => period.remove_race(payload["id"].as_u64().unwrap_or(u64::MAX))?,
+                "update_race" => period.update_race(
+                    payload["id"].as_u64().unwrap_or(u64::MAX),
+                    ledger::floats(&payload["race"])?,
+                )?,
+                "add_alias" => period.add_alias(&text("alias"), &text("name"))?,
+                "rename_player" => period.rename_player(&text("old"), &text("new"))?,
+                "merge_players" => period.merge_players(&text("name"), &text("other"))?,
+                "next_period" => period.close(),
+                "rank" => {
+                    let end = payload["end"].as_bool().unwrap_or(true);
+                    let at = period.rank_time(payload["at"].as_f64())?;
+                    let rankings = period.rankings_at(end, at);
+                    period.record_rank(end, at, &rankings);
+                }
+                _ => {
+                    return Err(GlickoError::py_err(format!(
+                        "Unknown event in ledger entry {}: {}",
+                        seq, entry.event
+                    )))
+                }
+            }
+            if period.ledger.lock().unwrap().head() != entry.hash {
+                return Err(GlickoError::py_err(format!(
+                    "Replaying ledger entry {} gave a different result",
+                    seq
+                )));
+            }
+        }
+
+        Ok(period)
+    }
+
+    fn load_constants(&mut self, constants: HashMap<String, f64>) -> Result<()> {
+        // presets and config files can leave out any constant
+        let constants: HashMap<&str, f64> =
+            constants.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        validate_constant_values(&constants)?;
+        self.constants = self.constants.with(&constants);
+        self.record_constants();
+
+        Ok(())
+    }
+
+    pub fn current_rating(&self, name: &str) -> (f64, f64) {
+        // a player's rating and deviation counting the races added so far
+        // this period, without closing the period. anyone we haven't seen
+        // yet would start from the initial rating
+        let name = self.resolve(name);
+        match self.players.get(&name) {
+            Some(p) if p.races.is_empty() => (p.glicko_rating.rating, p.glicko_rating.deviation),
+            Some(p) => {
+                let player_dict = self.process_1v1s(&name, p, false, None).0;
+                (player_dict["rating"], player_dict["deviation"])
+            }
+            None => (
+                self.constants.initial_rating,
+                self.constants.initial_deviation,
+            ),
+        }
+    }
+
+    fn rank_player(
+        &self,
+        name: &str,
+        player: &Player,
+        end: bool,
+        at: Option<NaiveDateTime>,
+    ) -> HashMap<&'static str, f64> {
+        let mut player_dict = if player.races.len() == 0 {
+            // player hasn't raced. only change RD and apply
+            self.process_inactive(player, at)
+        } else {
+            // player has raced, process their 1v1s and add them to the
+            // rankings hash map
+            self.process_1v1s(name, player, end, at).0
+        };
+        self.add_standing(player, &mut player_dict);
+
+        player_dict
+    }
+
     fn new_unrated(&mut self, name: &str) {
-        let initial_glicko = GlickoRating {
-            rating: self.constants.initial_rating,
-            deviation: self.constants.initial_deviation,
-            volatility: self.constants.initial_volatility,
+        let initial_glicko = match self.seeds.get(name) {
+            Some(seeds) if self.constants.seed_correlation > 0f64 => self.seeded_rating(seeds),
+            _ => GlickoRating {
+                rating: self.constants.initial_rating,
+                deviation: self.constants.initial_deviation,
+                volatility: self.constants.initial_volatility,
+            },
         };
         let new_player = Player {
             glicko_rating: initial_glicko,
             variance: 0.0,
             delta: 0.0,
-            inactive_periods: 0,
+            inactive_periods: 0f64,
+            race_count: 0,
+            unrated: true,
+            rated_at: None,
             races: Vec::with_capacity(20),
         };
         self.players.insert(name.to_string(), new_player);
     }
 
-    fn add_new_players(&mut self, race: &HashMap<String, f64>) -> Result<(), GlickoError> {
+    fn seeded_rating(&self, seeds: &[GlickoRating]) -> GlickoRating {
+        // pool the player's ratings from other categories weighted by their
+        // precision, then shrink the pooled rating toward the initial rating
+        // by the correlation between the two categories
+        let rho = self.constants.seed_correlation;
+        let initial_rating = self.constants.initial_rating;
+        let initial_deviation = self.constants.initial_deviation;
+        let precision: f64 = seeds.iter().map(|x| x.deviation.powi(2).recip()).sum();
+        let pooled_rating: f64 = seeds
+            .iter()
+            .map(|x| x.rating * x.deviation.powi(2).recip())
+            .sum::<f64>()
+            / precision;
+        let pooled_deviation: f64 = precision.recip().sqrt();
+
+        let rating = initial_rating + rho * (pooled_rating - initial_rating);
+        let deviation = (rho.powi(2) * pooled_deviation.powi(2)
+            + (1f64 - rho.powi(2)) * initial_deviation.powi(2))
+        .sqrt();
+
+        GlickoRating {
+            rating,
+            deviation: deviation.min(initial_deviation),
+            volatility: self.constants.initial_volatility,
+        }
+    }
+
+    fn add_new_players(&mut self, race: &HashMap<String, f64>) -> Result<(), Error> {
         let new_racers: Vec<&String> = race
             .keys()
             .filter(|x| self.players.contains_key(x.as_str()) == false)
@@ -288,79 +1501,131 @@ impl MultiPeriod {
         Ok(())
     }
 
-    fn make_pairings(
+    fn add_race(
         &mut self,
-        race: &HashMap<String, (f64, f64)>,
-        num_finishers: usize,
-    ) -> Result<(), GlickoError> {
-        let players: Vec<&String> = race.keys().collect();
-        let perms = players.iter().permutations(2);
-        let score = |p: f64, o: f64| -> f64 {
-            if p < o {
-                1f64
-            } else if p > o {
-                0f64
-            } else {
-                0.5f64
-            }
-        };
-        if num_finishers > self.constants.multi_cutoff as usize {
-            for pair in perms {
-                let opponent = Opponent {
-                    time: race[*pair[1]].0,
-                    glicko_score: score(race[*pair[1]].0, race[*pair[0]].0),
-                    normed_score: race[*pair[1]].1,
-                    rating: self.players[*pair[1]].glicko_rating,
-                };
-                let race_result = RaceResult {
-                    datetime: None,
-                    race_size: num_finishers as u32,
-                    player: (
-                        race[*pair[0]].0,
-                        score(race[*pair[0]].0, race[*pair[1]].0),
-                        race[*pair[0]].1,
-                    ),
-                    opponent: opponent,
-                };
-                self.players
-                    .entry(pair[0].to_string())
-                    .and_modify(|x| x.races.push(race_result));
+        race: &HashMap<String, f64>,
+        event: EventType,
+        datetime: Option<NaiveDateTime>,
+    ) -> Result<u64> {
+        let times = self.resolve_race(race)?;
+        let id = self.next_race_id;
+        self.next_race_id += 1;
+        let race = Race::new(event, datetime, times, self.constants.norm_factor(event));
+        self.index_race(id, race)?;
+
+        Ok(id)
+    }
+
+    fn index_race(&mut self, id: u64, race: Race) -> Result<(), Error> {
+        // each race is stored once. entrants only keep its id and their 1v1s
+        // are paired up from the stored race when they're ranked
+        self.add_new_players(&race.times)?;
+        for name in race.times.keys() {
+            self.players
+                .entry(name.to_string())
+                .and_modify(|x| x.races.push(id));
+        }
+        self.races.insert(id, race);
+
+        Ok(())
+    }
+
+    fn resolve(&self, name: &str) -> String {
+        self.aliases
+            .get(name)
+            .map_or(name, |x| x.as_str())
+            .to_string()
+    }
+
+    fn resolve_race(&self, race: &HashMap<String, f64>) -> Result<HashMap<String, f64>> {
+        let mut resolved: HashMap<String, f64> = HashMap::with_capacity(race.len());
+        for (name, time) in race.iter() {
+            let name = self.resolve(name);
+            if resolved.contains_key(&name) {
+                return Err(Error::new(format!(
+                    "Invalid race passed to method: {} is in the race more than once",
+                    name
+                )));
             }
-        } else {
-            for pair in perms {
-                let opponent = Opponent {
-                    time: race[*pair[1]].0,
-                    glicko_score: score(race[*pair[1]].0, race[*pair[0]].0),
-                    normed_score: race[*pair[1]].1,
-                    rating: self.players[*pair[1]].glicko_rating,
-                };
-                let race_result = RaceResult {
-                    datetime: None,
-                    race_size: num_finishers as u32,
-                    player: (
-                        race[*pair[0]].0,
-                        score(race[*pair[0]].0, race[*pair[1]].0),
-                        race[*pair[0]].1,
-                    ),
-                    opponent: opponent,
-                };
-                self.players
-                    .entry(pair[0].to_string())
-                    .and_modify(|x| x.races.push(race_result));
+            resolved.insert(name, *time);
+        }
+
+        Ok(resolved)
+    }
+
+    #[cfg(feature = "python")]
+    fn point_aliases(&mut self, old: &str, new: &str) {
+        // keep the alias table flat so every alias resolves in one lookup
+        for target in self.aliases.values_mut().filter(|x| x.as_str() == old) {
+            *target = new.to_string();
+        }
+        self.aliases.insert(old.to_string(), new.to_string());
+    }
+
+    #[cfg(feature = "python")]
+    fn unindex_race(&mut self, id: u64, times: &HashMap<String, f64>) {
+        // drop the race from each entrant. anyone who was only in the period
+        // because of this race is removed entirely
+        for name in times.keys() {
+            let remove = match self.players.get_mut(name) {
+                Some(p) => {
+                    p.races.retain(|&r| r != id);
+                    p.unrated && p.races.is_empty()
+                }
+                None => false,
+            };
+            if remove {
+                self.players.remove(name);
             }
         }
-        Ok(())
     }
 
-    fn process_1v1s(&self, player: &Player, end: bool) -> HashMap<&str, f64> {
-        let mut player_dict: HashMap<&str, f64> = HashMap::with_capacity(6);
+    fn make_pairings<'a>(
+        &'a self,
+        name: &'a str,
+        player: &'a Player,
+    ) -> impl Iterator<Item = RaceResult> + 'a {
+        // every 1v1 the player had against the rest of each of their races
+        player.races.iter().flat_map(move |id| {
+            let race = &self.races[id];
+            let p = race.normed[name];
+            race.normed
+                .iter()
+                .filter(move |(o, _)| o.as_str() != name)
+                .map(move |(o, t)| RaceResult {
+                    event: race.event,
+                    race_size: race.num_finishers,
+                    player: (p.0, score(p.0, t.0), p.1),
+                    opponent: Opponent {
+                        time: t.0,
+                        glicko_score: score(t.0, p.0),
+                        normed_score: t.1,
+                        rating: self.players[o].glicko_rating,
+                    },
+                })
+        })
+    }
+
+    fn process_1v1s(
+        &self,
+        name: &str,
+        player: &Player,
+        end: bool,
+        at: Option<NaiveDateTime>,
+    ) -> (HashMap<&'static str, f64>, Convergence) {
+        let mut player_dict: HashMap<&'static str, f64> = HashMap::with_capacity(6);
+        let mut convergence = Convergence {
+            iterations: math::Iterations::default(),
+            error: None,
+        };
         let initial_rating = self.constants.initial_rating;
         let mut converted_rating = player.glicko_rating.convert_to(initial_rating);
         let mut v_inv = sanitize_v(player.variance.recip());
         let mut delta = player.delta;
         let tau = self.constants.glicko_tau;
+        let (elapsed, until) = self.elapsed_periods(player, at);
 
-        for r in &player.races {
+        for r in self.make_pairings(name, player) {
             let ndiff: f64 = (r.player.1 - r.opponent.normed_score).abs();
             let tdiff: f64 = (r.player.0 - r.opponent.time).abs();
             let size = r.race_size;
@@ -388,20 +1653,30 @@ impl MultiPeriod {
             }
             let expected_score =
                 1f64 / (1f64 + (-weight * (converted_rating.rating - opp.rating)).exp());
-            v_inv += weight.powi(2) * expected_score * (1f64 - expected_score);
-            delta += weight * (r.player.1 as f64 - expected_score);
+            // the event weight scales how much the 1v1 counts towards the
+            // period rather than the expected score itself
+            let m = self.constants.event_weight(r.event);
+            v_inv += m * weight.powi(2) * expected_score * (1f64 - expected_score);
+            delta += m * weight * (r.player.1 as f64 - expected_score);
         }
         if v_inv != 0f64 {
             let var = 1f64 / v_inv;
             let change = var * delta;
-            let new_sigma = math::get_sigma(
+            let (sigma_result, iterations) = math::get_sigma(
                 tau,
                 converted_rating.deviation,
                 converted_rating.volatility,
                 change,
                 var,
             );
-            let phi_star: f64 = (converted_rating.deviation.powi(2) + new_sigma.powi(2)).sqrt();
+            // if we can't find a new volatility, keep the old one
+            let new_sigma = sigma_result.unwrap_or(converted_rating.volatility);
+            convergence = Convergence {
+                iterations,
+                error: sigma_result.err(),
+            };
+            let phi_star: f64 =
+                (converted_rating.deviation.powi(2) + elapsed * new_sigma.powi(2)).sqrt();
             converted_rating.deviation = 1f64 / ((1f64 / phi_star.powi(2)) + (1f64 / var)).sqrt();
             converted_rating.rating =
                 converted_rating.rating + converted_rating.deviation.powi(2) * delta;
@@ -439,62 +1714,223 @@ impl MultiPeriod {
             }
         }
 
-        player_dict
+        // mid-period ratings are still as of the start of the period
+        let rated_at = if end { until } else { player.rated_at };
+        if let Some(rated_at) = rated_at {
+            player_dict.insert("rated_at", to_timestamp(rated_at));
+        }
+
+        (player_dict, convergence)
     }
 
-    fn process_inactive(&self, player: &Player) -> HashMap<&str, f64> {
-        let mut player_dict: HashMap<&str, f64> = HashMap::with_capacity(6);
+    fn add_standing(&self, player: &Player, player_dict: &mut HashMap<&str, f64>) {
+        // the score we sort leaderboards by holds a player's deviation against
+        // them so a lucky newcomer can't jump over established players
+        let score = player_dict["rating"] - self.constants.score_k * player_dict["deviation"];
+        player_dict.insert("score", score);
+
+        // a player is established once they've run enough races, their
+        // deviation has come down far enough and they haven't been away too long
+        let races = player.race_count as usize + player.races.len();
+        let established = races >= self.constants.provisional_races as usize
+            && player_dict["deviation"] <= self.constants.provisional_deviation
+            && player_dict["inactive_periods"] <= self.constants.provisional_periods as f64;
+        player_dict.insert("races", races as f64);
+        player_dict.insert("provisional", if established { 0f64 } else { 1f64 });
+    }
+
+    fn process_inactive(
+        &self,
+        player: &Player,
+        at: Option<NaiveDateTime>,
+    ) -> HashMap<&'static str, f64> {
+        let mut player_dict: HashMap<&'static str, f64> = HashMap::with_capacity(7);
         let initial_rating = self.constants.initial_rating;
         let mut converted_rating = player.glicko_rating.convert_to(initial_rating);
-        let inactive_periods: u32 = player.inactive_periods + 1;
-        let phi_star: f64 =
-            (converted_rating.deviation.powi(2) + converted_rating.volatility.powi(2)).sqrt();
+        // with continuous periods, time away is counted in fractions of a
+        // period, so ranking again at the same time changes nothing
+        let (elapsed, until) = self.elapsed_periods(player, at);
+        let inactive_periods: f64 = player.inactive_periods + elapsed;
+        let phi_star: f64 = (converted_rating.deviation.powi(2)
+            + elapsed * converted_rating.volatility.powi(2))
+        .sqrt();
         converted_rating.deviation = phi_star;
         let mut new_rating = converted_rating.convert_from(initial_rating);
-        if player.inactive_periods > 0 {
-            new_rating.decay_score(player.inactive_periods);
+        if player.inactive_periods > 0f64 {
+            new_rating.decay_score(player.inactive_periods, elapsed);
         }
         player_dict.insert("rating", new_rating.rating);
         player_dict.insert("deviation", new_rating.deviation);
         player_dict.insert("volatility", new_rating.volatility);
         player_dict.insert("variance", 0f64);
         player_dict.insert("delta", 0f64);
-        player_dict.insert("inactive_periods", inactive_periods as f64);
+        player_dict.insert("inactive_periods", inactive_periods);
+        if let Some(rated_at) = until.or(player.rated_at) {
+            player_dict.insert("rated_at", to_timestamp(rated_at));
+        }
 
         player_dict
     }
 }
 
-fn validate_constants(constants: &HashMap<&str, f64>) -> PyResult<()> {
-    const REQUIRED_CONSTANTS: [&str; 8] = [
-        "tau",
-        "multi_slope",
-        "multi_cutoff",
-        "norm_factor",
-        "victory_margin",
-        "initial_rating",
-        "initial_deviation",
-        "initial_volatility",
-    ];
+fn from_timestamp(timestamp: f64) -> Option<NaiveDateTime> {
+    if !timestamp.is_finite() {
+        return None;
+    }
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|x| x.naive_utc())
+}
+
+pub(crate) fn to_timestamp(datetime: NaiveDateTime) -> f64 {
+    Utc.from_utc_datetime(&datetime).timestamp() as f64
+}
+
+fn score(p: f64, o: f64) -> f64 {
+    if p < o {
+        1f64
+    } else if p > o {
+        0f64
+    } else {
+        0.5f64
+    }
+}
+
+#[cfg(feature = "python")]
+fn place(board: &mut [(String, HashMap<&str, f64>)]) {
+    // players with the same score share a position. percentile is the share
+    // of the rest of the board with a lower score
+    let n = board.len();
+    let mut i: usize = 0;
+    while i < n {
+        let score = board[i].1["score"];
+        let ties = board[i..]
+            .iter()
+            .take_while(|x| x.1["score"] == score)
+            .count();
+        let below = n - i - ties;
+        let percentile = if n > 1 {
+            100f64 * below as f64 / (n - 1) as f64
+        } else {
+            100f64
+        };
+        for entry in board[i..i + ties].iter_mut() {
+            entry.1.insert("position", (i + 1) as f64);
+            entry.1.insert("tied", if ties > 1 { 1f64 } else { 0f64 });
+            entry.1.insert("percentile", percentile);
+        }
+        i += ties;
+    }
+}
+
+#[cfg(feature = "python")]
+fn merge(player: Player, other: Player) -> Player {
+    // keep whichever pre-period rating we're more certain of. the merged
+    // player's races are paired again by the caller
+    let (kept, dropped) = if player.glicko_rating.deviation <= other.glicko_rating.deviation {
+        (player, other)
+    } else {
+        (other, player)
+    };
+
+    Player {
+        inactive_periods: kept.inactive_periods.min(dropped.inactive_periods),
+        race_count: kept.race_count + dropped.race_count,
+        unrated: kept.unrated && dropped.unrated,
+        races: Vec::with_capacity(20),
+        ..kept
+    }
+}
+
+const OPTIONAL_CONSTANTS: [&str; 13] = [
+    "seed_correlation",
+    "casual_weight",
+    "weekly_weight",
+    "qualifier_weight",
+    "tournament_weight",
+    "async_weight",
+    "async_norm_factor",
+    "async_window",
+    "provisional_races",
+    "provisional_deviation",
+    "provisional_periods",
+    "score_k",
+    "period_days",
+];
+const REQUIRED_CONSTANTS: [&str; 8] = [
+    "tau",
+    "multi_slope",
+    "multi_cutoff",
+    "norm_factor",
+    "victory_margin",
+    "initial_rating",
+    "initial_deviation",
+    "initial_volatility",
+];
+
+fn validate_constants(constants: &HashMap<&str, f64>) -> Result<()> {
     if REQUIRED_CONSTANTS
         .iter()
         .all(|&k| constants.contains_key(k))
         == false
     {
-        return Err(GlickoError::py_err(
-            "Not all Glicko constants found in dict",
-        ));
+        return Err(Error::new("Not all Glicko constants found in dict"));
     }
-    if constants.keys().all(|x| REQUIRED_CONSTANTS.contains(x)) == false {
-        return Err(GlickoError::py_err(
-            "Malformed constants dict passed to method",
-        ));
+
+    validate_constant_values(constants)
+}
+
+fn validate_constant_values(constants: &HashMap<&str, f64>) -> Result<()> {
+    if !constants
+        .keys()
+        .all(|x| REQUIRED_CONSTANTS.contains(x) || OPTIONAL_CONSTANTS.contains(x))
+    {
+        return Err(Error::new("Malformed constants dict passed to method"));
+    }
+    for (&name, &value) in constants.iter() {
+        validate_constant(name, value)?;
     }
 
     Ok(())
 }
 
-fn validate_players(players: &HashMap<String, HashMap<String, f64>>) -> PyResult<()> {
+fn validate_constant(name: &str, value: f64) -> Result<()> {
+    // anything that would make the ratings NaN or meaningless is rejected
+    // before it's set, not found later when a period is ranked
+    let invalid = |message: &str| Err(Error::new(format!("{} {}", name, message)));
+    if !value.is_finite() {
+        return invalid("must be a finite number");
+    }
+    match name {
+        "seed_correlation" => validate_correlation(value),
+        "period_days" => validate_period_days(value),
+        x if x.ends_with("_weight") => validate_event_weight(value),
+        "tau" | "initial_deviation" | "initial_volatility" | "provisional_deviation"
+            if value <= 0f64 =>
+        {
+            invalid("must be greater than 0")
+        }
+        "multi_slope" if !(0f64..1f64).contains(&value) => {
+            invalid("must be at least 0 and less than 1")
+        }
+        // the margin is in whole seconds, and a margin of 0 would divide by
+        // zero for a tie
+        "victory_margin" if value < 1f64 => invalid("must be at least 1 second"),
+        "norm_factor"
+        | "async_norm_factor"
+        | "async_window"
+        | "multi_cutoff"
+        | "provisional_races"
+        | "provisional_periods"
+            if value < 0f64 =>
+        {
+            invalid("can't be negative")
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_players(players: &HashMap<String, HashMap<String, f64>>) -> Result<()> {
     const REQUIRED_KEYS: [&str; 6] = [
         "rating",
         "deviation",
@@ -503,16 +1939,34 @@ fn validate_players(players: &HashMap<String, HashMap<String, f64>>) -> PyResult
         "delta",
         "inactive_periods",
     ];
+    const OPTIONAL_KEYS: [&str; 4] = ["races", "provisional", "score", "rated_at"];
 
     for m in players.values() {
         if REQUIRED_KEYS.iter().all(|&k| m.contains_key(k)) == false {
-            return Err(GlickoError::py_err(
-                "Not all player attributes found in dict",
-            ));
+            return Err(Error::new("Not all player attributes found in dict"));
         }
-        if m.keys().all(|x| REQUIRED_KEYS.contains(&x.as_str())) == false {
-            return Err(GlickoError::py_err(
-                "Malformed player dict passed to method",
+        if !m
+            .keys()
+            .all(|x| REQUIRED_KEYS.contains(&x.as_str()) || OPTIONAL_KEYS.contains(&x.as_str()))
+        {
+            return Err(Error::new("Malformed player dict passed to method"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+fn validate_seeds(seeds: &HashMap<String, Vec<HashMap<String, f64>>>) -> Result<()> {
+    const REQUIRED_KEYS: [&str; 2] = ["rating", "deviation"];
+
+    for m in seeds.values().flatten() {
+        if !REQUIRED_KEYS.iter().all(|&k| m.contains_key(k)) {
+            return Err(Error::new("Not all seed attributes found in dict"));
+        }
+        if m["deviation"] <= 0f64 {
+            return Err(Error::new(
+                "Invalid seed passed to method: Deviation must be positive",
             ));
         }
     }
@@ -520,19 +1974,94 @@ fn validate_players(players: &HashMap<String, HashMap<String, f64>>) -> PyResult
     Ok(())
 }
 
-fn validate_race(race: &HashMap<String, f64>) -> PyResult<()> {
+fn validate_correlation(correlation: f64) -> Result<()> {
+    if !(0f64..=1f64).contains(&correlation) {
+        return Err(Error::new("Seed correlation must be between 0 and 1"));
+    }
+
+    Ok(())
+}
+
+fn validate_timestamps(
+    timestamps: Option<Vec<f64>>,
+    num_races: usize,
+) -> Result<Vec<Option<NaiveDateTime>>> {
+    // one unix timestamp per race, in the same order as the races
+    match timestamps {
+        None => Ok(vec![None; num_races]),
+        Some(t) if t.len() != num_races => {
+            Err(Error::new("Must pass exactly one timestamp for each race"))
+        }
+        Some(t) => t
+            .into_iter()
+            .map(|x| match from_timestamp(x) {
+                Some(d) => Ok(Some(d)),
+                None => Err(Error::new(format!("Invalid timestamp {}", x))),
+            })
+            .collect(),
+    }
+}
+
+fn validate_period_days(days: f64) -> Result<()> {
+    if !(days.is_finite() && days >= 0f64) {
+        return Err(Error::new(
+            "Period length must be a non-negative number of days",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+fn validate_async_race(
+    race: &HashMap<String, (f64, f64)>,
+    window: f64,
+) -> Result<(HashMap<String, f64>, f64)> {
+    // returns the race's times along with the time it opened, which we take
+    // to be the earliest submission. any finish submitted after the window
+    // closes is counted as a forfeit
+    if race.values().any(|x| !x.0.is_nan() && !x.1.is_finite()) {
+        return Err(Error::new(
+            "Invalid async race passed to method: Finish without a submission time",
+        ));
+    }
+    let opened: f64 = race
+        .values()
+        .filter(|x| x.1.is_finite())
+        .map(|x| x.1)
+        .fold(f64::NAN, f64::min);
+    let times: HashMap<String, f64> = race
+        .iter()
+        .map(|(k, v)| match v {
+            (_, s) if window > 0f64 && s - opened > window => (k.to_string(), f64::NAN),
+            (t, _) => (k.to_string(), *t),
+        })
+        .collect();
+
+    Ok((times, opened))
+}
+
+fn validate_event_weight(weight: f64) -> Result<()> {
+    if !(weight.is_finite() && weight > 0f64) {
+        return Err(Error::new("Event weight must be a positive number"));
+    }
+
+    Ok(())
+}
+
+fn validate_race(race: &HashMap<String, f64>) -> Result<()> {
     // confirm that the race has:
     // 1. At least two players
     // 2. At least one non-forfeiting player
     if race.len() < 2 {
-        return Err(GlickoError::py_err(
+        return Err(Error::new(
             "Invalid race passed to method: Less than two racers",
         ));
     }
 
     let times: Vec<&f64> = race.values().filter(|x| x.is_nan() == false).collect();
     if times.len() < 1 {
-        return Err(GlickoError::py_err(
+        return Err(Error::new(
             "Invalid race passed to method: Less than one finisher",
         ));
     }
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+#[cfg(feature = "wasm")]
+use serde_json::Value;
+
+use crate::ledger;
+use crate::{Error, Result};
+
+// the bindings outside python take and return json strings. json has no NaN,
+// so forfeits and other missing numbers are null both ways
+
+pub type Players = HashMap<String, HashMap<String, f64>>;
+
+pub fn constants(text: &str) -> Result<HashMap<String, f64>> {
+    parse(text)
+}
+
+pub fn players(text: &str) -> Result<Players> {
+    let players: HashMap<String, HashMap<String, Option<f64>>> = parse(text)?;
+
+    Ok(players
+        .into_iter()
+        .map(|(name, player)| (name, nan_for_null(player)))
+        .collect())
+}
+
+pub fn races(text: &str) -> Result<Vec<HashMap<String, f64>>> {
+    // one race or a list of them
+    let races: Races = parse(text)?;
+    let races = match races {
+        Races::One(race) => vec![race],
+        Races::Many(races) => races,
+    };
+
+    Ok(races.into_iter().map(nan_for_null).collect())
+}
+
+pub fn timestamps(text: &str) -> Result<Option<Vec<f64>>> {
+    match text.trim() {
+        "" | "null" => Ok(None),
+        text => parse(text).map(Some),
+    }
+}
+
+pub fn to_string<K: AsRef<str>, L: AsRef<str>>(rankings: &HashMap<K, HashMap<L, f64>>) -> String {
+    ledger::nested(rankings).to_string()
+}
+
+#[cfg(feature = "wasm")]
+pub fn ids(ids: &[u64]) -> String {
+    Value::from(ids).to_string()
+}
+
+fn parse<T: DeserializeOwned>(text: &str) -> Result<T> {
+    serde_json::from_str(text).map_err(|e| Error::new(format!("Invalid JSON: {}", e)))
+}
+
+fn nan_for_null(map: HashMap<String, Option<f64>>) -> HashMap<String, f64> {
+    map.into_iter()
+        .map(|(k, v)| (k, v.unwrap_or(f64::NAN)))
+        .collect()
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Races {
+    One(HashMap<String, Option<f64>>),
+    Many(Vec<HashMap<String, Option<f64>>>),
+}
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
+use pyo3::prelude::*;
+use rusqlite::{params, Connection, Transaction};
+
+use crate::glicko::{to_timestamp, MultiPeriod};
+use crate::GlickoError;
+
+// every period's races are kept, and each closed period keeps a snapshot of
+// everyone's ratings at its end that never changes after. players added to a
+// league are kept with the period they joined in, so a period starts from the
+// last snapshot plus anyone added since. periods are numbered from 1 and the
+// open period is always the one with the highest id
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS constants (
+        name TEXT PRIMARY KEY,
+        value REAL NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS periods (
+        id INTEGER PRIMARY KEY,
+        closed_at REAL
+    );
+    CREATE TABLE IF NOT EXISTS races (
+        id INTEGER PRIMARY KEY,
+        period INTEGER NOT NULL REFERENCES periods(id),
+        event TEXT NOT NULL,
+        timestamp REAL
+    );
+    CREATE TABLE IF NOT EXISTS race_times (
+        race INTEGER NOT NULL REFERENCES races(id),
+        player TEXT NOT NULL,
+        time REAL,
+        PRIMARY KEY (race, player)
+    );
+    CREATE TABLE IF NOT EXISTS ratings (
+        period INTEGER NOT NULL,
+        player TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value REAL,
+        PRIMARY KEY (period, player, key)
+    );
+    CREATE TABLE IF NOT EXISTS added_players (
+        period INTEGER NOT NULL,
+        player TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value REAL,
+        PRIMARY KEY (period, player, key)
+    );
+    INSERT OR IGNORE INTO periods (id) VALUES (1);
+";
+
+// a race's event type, timestamp and times
+type StoredRace = (String, Option<f64>, HashMap<String, f64>);
+
+/// A league stored in a SQLite database, opened or created at path.
+#[pyclass]
+#[text_signature = "(path)"]
+pub struct League {
+    conn: Connection,
+}
+
+#[pymethods]
+impl League {
+    #[new]
+    fn new(obj: &PyRawObject, path: &str) -> PyResult<()> {
+        obj.init(League::open(path)?);
+
+        Ok(())
+    }
+
+    /// The number of the open period.
+    #[getter]
+    fn get_period(&self) -> PyResult<i64> {
+        self.open_period()
+    }
+
+    /// Set the league's constants, the same way as MultiPeriod.set_constants().
+    #[text_signature = "($self, constants)"]
+    fn set_constants(&mut self, constants: HashMap<&str, f64>) -> PyResult<()> {
+        MultiPeriod::default().set_constant_map(constants.clone())?;
+        let tx = self.conn.transaction().map_err(db_error)?;
+        tx.execute("DELETE FROM constants", params![])
+            .map_err(db_error)?;
+        for (name, value) in constants.iter() {
+            tx.execute(
+                "INSERT INTO constants (name, value) VALUES (?1, ?2)",
+                params![name, value],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// Add players to the ratings the open period starts from.
+    #[text_signature = "($self, players)"]
+    fn add_players(&mut self, players: HashMap<String, HashMap<String, f64>>) -> PyResult<()> {
+        MultiPeriod::default().add_player_map(players.clone())?;
+        let period = self.open_period()?;
+        let tx = self.conn.transaction().map_err(db_error)?;
+        save_ratings(&tx, "added_players", period, &players)?;
+        tx.commit().map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// Add races to the open period, the same way as MultiPeriod.add_races().
+    #[text_signature = "($self, races, event='weekly', timestamps=None)"]
+    #[args(event = "\"weekly\"", timestamps = "None")]
+    fn add_races(
+        &mut self,
+        races: Vec<HashMap<String, f64>>,
+        event: &str,
+        timestamps: Option<Vec<f64>>,
+    ) -> PyResult<()> {
+        // check the races against the open period before storing anything
+        self.load()?
+            .add_race_list(races.clone(), event, timestamps.clone())?;
+        let period = self.open_period()?;
+        let tx = self.conn.transaction().map_err(db_error)?;
+        for (i, race) in races.iter().enumerate() {
+            let timestamp = timestamps.as_ref().map(|x| x[i]);
+            tx.execute(
+                "INSERT INTO races (period, event, timestamp) VALUES (?1, ?2, ?3)",
+                params![period, event, timestamp],
+            )
+            .map_err(db_error)?;
+            let race_id = tx.last_insert_rowid();
+            for (player, time) in race.iter() {
+                tx.execute(
+                    "INSERT INTO race_times (race, player, time) VALUES (?1, ?2, ?3)",
+                    params![race_id, player, time],
+                )
+                .map_err(db_error)?;
+            }
+        }
+        tx.commit().map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// A MultiPeriod with everything in the open period so far.
+    #[text_signature = "($self)"]
+    fn current_period(&self, py: Python) -> PyResult<Py<MultiPeriod>> {
+        Py::new(py, self.load()?)
+    }
+
+    /// Rank the open period, store everyone's ratings and start the next one.
+    ///
+    /// Returns the rankings, the same as MultiPeriod.rank(at=at).
+    #[text_signature = "($self, at=None)"]
+    #[args(at = "None")]
+    fn close_period(&mut self, at: Option<f64>) -> PyResult<HashMap<String, HashMap<String, f64>>> {
+        let period = self.load()?;
+        let rank_time = period.rank_time(at)?;
+        let rankings = owned(period.rankings_at(true, rank_time));
+        // closed_at is the time everyone was rated as of, or now if the
+        // period had nothing to go by
+        let closed_at = to_timestamp(rank_time.unwrap_or_else(|| Utc::now().naive_utc()));
+        let closing = self.open_period()?;
+        // the snapshot and the new period are one transaction, all or nothing
+        let tx = self.conn.transaction().map_err(db_error)?;
+        save_ratings(&tx, "ratings", closing, &rankings)?;
+        tx.execute(
+            "UPDATE periods SET closed_at = ?1 WHERE id = ?2",
+            params![closed_at, closing],
+        )
+        .map_err(db_error)?;
+        tx.execute("INSERT INTO periods (id) VALUES (?1)", params![closing + 1])
+            .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+
+        Ok(rankings)
+    }
+
+    /// The ratings at the end of a closed period, the last one by default.
+    #[text_signature = "($self, period=None)"]
+    #[args(period = "None")]
+    fn rankings(&self, period: Option<i64>) -> PyResult<HashMap<String, HashMap<String, f64>>> {
+        let last_closed = self.open_period()? - 1;
+        match period.unwrap_or(last_closed) {
+            p if (1..=last_closed).contains(&p) => self.ratings("ratings", p),
+            p => Err(GlickoError::py_err(format!(
+                "Period {} hasn't been closed",
+                p
+            ))),
+        }
+    }
+}
+
+impl League {
+    pub fn open(path: &str) -> PyResult<League> {
+        let conn = Connection::open(path).map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+
+        Ok(League { conn })
+    }
+
+    pub fn load(&self) -> PyResult<MultiPeriod> {
+        // rebuild the open period from the database
+        let mut period = MultiPeriod::default();
+        let constants = self.constants()?;
+        if !constants.is_empty() {
+            period.set_constant_map(constants.iter().map(|(k, v)| (k.as_str(), *v)).collect())?;
+        }
+        let open = self.open_period()?;
+        period.add_player_map(self.starting_ratings(open)?)?;
+        for (event, timestamp, race) in self.races(open)? {
+            period.add_race_list(vec![race], &event, timestamp.map(|x| vec![x]))?;
+        }
+
+        Ok(period)
+    }
+
+    fn open_period(&self) -> PyResult<i64> {
+        self.conn
+            .query_row("SELECT MAX(id) FROM periods", params![], |row| row.get(0))
+            .map_err(db_error)
+    }
+
+    fn constants(&self) -> PyResult<HashMap<String, f64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, value FROM constants")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(db_error)?;
+
+        rows.collect::<Result<_, _>>().map_err(db_error)
+    }
+
+    fn starting_ratings(&self, period: i64) -> PyResult<HashMap<String, HashMap<String, f64>>> {
+        // the last snapshot, with anyone added to this period on top
+        let mut ratings = self.ratings("ratings", period - 1)?;
+        ratings.extend(self.ratings("added_players", period)?);
+
+        Ok(ratings)
+    }
+
+    fn ratings(&self, table: &str, period: i64) -> PyResult<HashMap<String, HashMap<String, f64>>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT player, key, value FROM {} WHERE period = ?1",
+                table
+            ))
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![period], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(db_error)?;
+        let mut ratings: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        for row in rows {
+            let (player, key, value): (String, String, Option<f64>) = row.map_err(db_error)?;
+            ratings
+                .entry(player)
+                .or_default()
+                .insert(key, value.unwrap_or(f64::NAN));
+        }
+
+        Ok(ratings)
+    }
+
+    fn races(&self, period: i64) -> PyResult<Vec<StoredRace>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT races.id, event, timestamp, player, time FROM races
+                 JOIN race_times ON race_times.race = races.id
+                 WHERE period = ?1",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![period], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .map_err(db_error)?;
+        // races go back in the order they were added
+        let mut races: BTreeMap<i64, StoredRace> = BTreeMap::new();
+        for row in rows {
+            let (id, event, timestamp, player, time): (
+                i64,
+                String,
+                Option<f64>,
+                String,
+                Option<f64>,
+            ) = row.map_err(db_error)?;
+            races
+                .entry(id)
+                .or_insert_with(|| (event, timestamp, HashMap::new()))
+                .2
+                .insert(player, time.unwrap_or(f64::NAN));
+        }
+
+        Ok(races.into_values().collect())
+    }
+}
+
+fn save_ratings(
+    tx: &Transaction,
+    table: &str,
+    period: i64,
+    players: &HashMap<String, HashMap<String, f64>>,
+) -> PyResult<()> {
+    // sqlite stores NaN as null, which reads back as NaN. table is ratings
+    // or added_players, which have the same columns
+    for (player, ratings) in players.iter() {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE period = ?1 AND player = ?2", table),
+            params![period, player],
+        )
+        .map_err(db_error)?;
+        for (key, value) in ratings.iter() {
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (period, player, key, value) VALUES (?1, ?2, ?3, ?4)",
+                    table
+                ),
+                params![period, player, key, value],
+            )
+            .map_err(db_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn owned(rankings: HashMap<&str, HashMap<&str, f64>>) -> HashMap<String, HashMap<String, f64>> {
+    rankings
+        .into_iter()
+        .map(|(name, player)| {
+            let player = player
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+            (name.to_string(), player)
+        })
+        .collect()
+}
+
+fn db_error(e: rusqlite::Error) -> PyErr {
+    GlickoError::py_err(format!("League database error: {}", e))
+}
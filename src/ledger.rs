@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "python")]
+use crate::{Error, Result};
+
+// an append-only log of everything done to a period. each entry's hash covers
+// the entry before it, so changing or dropping an entry breaks every hash
+// after it
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+    entries: Vec<Entry>,
+}
+
+// without python nothing reads a ledger back, so only the hashes are kept
+#[derive(Clone, Debug)]
+pub struct Entry {
+    #[cfg(feature = "python")]
+    pub event: String,
+    #[cfg(feature = "python")]
+    pub payload: Value,
+    #[cfg(feature = "python")]
+    pub previous: String,
+    pub hash: String,
+}
+
+impl Ledger {
+    pub fn record(&mut self, event: &str, payload: Value) {
+        let previous = self.head().to_string();
+        let hash = hash_entry(&previous, event, &payload);
+        self.entries.push(Entry {
+            #[cfg(feature = "python")]
+            event: event.to_string(),
+            #[cfg(feature = "python")]
+            payload,
+            #[cfg(feature = "python")]
+            previous,
+            hash,
+        });
+    }
+
+    pub fn head(&self) -> &str {
+        self.entries.last().map_or("", |x| x.hash.as_str())
+    }
+
+    #[cfg(feature = "python")]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    #[cfg(feature = "python")]
+    pub fn to_lines(&self) -> String {
+        // one json object per line so a saved ledger can be appended to
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(seq, x)| {
+                let line = json!({
+                    "seq": seq,
+                    "event": x.event,
+                    "payload": x.payload,
+                    "previous": x.previous,
+                    "hash": x.hash,
+                });
+                format!("{}\n", line)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "python")]
+pub fn parse(contents: &str) -> Result<Vec<Entry>> {
+    // read a saved ledger back, checking that it hasn't been tampered with
+    let mut entries: Vec<Entry> = Vec::new();
+    for (seq, line) in contents
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .enumerate()
+    {
+        let invalid = || Error::new(format!("Invalid ledger entry {}", seq));
+        let line: Value = serde_json::from_str(line).map_err(|_| invalid())?;
+        let field = |k: &str| line.get(k).and_then(|x| x.as_str()).map(|x| x.to_string());
+        let entry = match (
+            field("event"),
+            line.get("payload"),
+            field("previous"),
+            field("hash"),
+        ) {
+            (Some(event), Some(payload), Some(previous), Some(hash)) => Entry {
+                event,
+                payload: payload.clone(),
+                previous,
+                hash,
+            },
+            _ => return Err(invalid()),
+        };
+        let expected_previous = entries.last().map_or("", |x| x.hash.as_str());
+        if entry.previous != expected_previous
+            || entry.hash != hash_entry(&entry.previous, &entry.event, &entry.payload)
+        {
+            return Err(Error::new(format!(
+                "Ledger entry {} doesn't match its hash",
+                seq
+            )));
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+pub fn hash_entry(previous: &str, event: &str, payload: &Value) -> String {
+    // serde_json keeps object keys sorted, so the same payload always
+    // serializes to the same string
+    hex(&Sha256::digest(
+        format!("{}\n{}\n{}", previous, event, payload).as_bytes(),
+    ))
+}
+
+#[cfg(feature = "python")]
+pub fn hash_value(value: &Value) -> String {
+    hex(&Sha256::digest(value.to_string().as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+// json has no NaN, so forfeits and other missing numbers are stored as null
+
+pub fn number(x: f64) -> Value {
+    serde_json::Number::from_f64(x).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(feature = "python")]
+pub fn float(value: &Value) -> f64 {
+    value.as_f64().unwrap_or(f64::NAN)
+}
+
+pub fn numbers<K: AsRef<str>>(map: &HashMap<K, f64>) -> Value {
+    let object: Map<String, Value> = map
+        .iter()
+        .map(|(k, v)| (k.as_ref().to_string(), number(*v)))
+        .collect();
+
+    Value::Object(object)
+}
+
+#[cfg(feature = "python")]
+pub fn floats(value: &Value) -> Result<HashMap<String, f64>> {
+    match value.as_object() {
+        Some(object) => Ok(object.iter().map(|(k, v)| (k.clone(), float(v))).collect()),
+        None => Err(Error::new("Invalid ledger payload")),
+    }
+}
+
+pub fn nested<K: AsRef<str>, L: AsRef<str>>(map: &HashMap<K, HashMap<L, f64>>) -> Value {
+    let object: BTreeMap<String, Value> = map
+        .iter()
+        .map(|(k, v)| (k.as_ref().to_string(), numbers(v)))
+        .collect();
+
+    json!(object)
+}
+
+#[cfg(feature = "python")]
+pub fn nested_floats(value: &Value) -> Result<HashMap<String, HashMap<String, f64>>> {
+    match value.as_object() {
+        Some(object) => object
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), floats(v)?)))
+            .collect(),
+        None => Err(Error::new("Invalid ledger payload")),
+    }
+}
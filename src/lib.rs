@@ -1,14 +1,45 @@
+#[cfg(feature = "python")]
 use pyo3::create_exception;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
-mod glicko;
-mod math;
+#[cfg(all(feature = "python", any(feature = "wasm", feature = "capi")))]
+compile_error!("the wasm and capi features build without python: add --no-default-features");
 
+pub mod backtest;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod config;
+mod error;
+pub mod glicko;
+#[cfg(any(feature = "wasm", feature = "capi"))]
+mod json;
+#[cfg(feature = "sqlite")]
+pub mod league;
+mod ledger;
+pub mod math;
+pub mod simulate;
+#[cfg(feature = "python")]
+mod stats;
+#[cfg(feature = "python")]
+mod table;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{Error, Result};
+
+#[cfg(feature = "python")]
 #[pymodule]
 fn randorank(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<glicko::MultiPeriod>()?;
+    #[cfg(feature = "sqlite")]
+    m.add_class::<league::League>()?;
+    stats::add_functions(m)?;
+    simulate::add_functions(m)?;
+    backtest::add_functions(m)?;
     m.add("GlickoError", py.get_type::<GlickoError>())?;
     Ok(())
 }
 
+#[cfg(feature = "python")]
 create_exception!(randorank, GlickoError, pyo3::exceptions::Exception);
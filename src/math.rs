@@ -47,8 +47,58 @@ pub fn normalize_race(
     normed_race
 }
 
-pub fn get_sigma(tau: f64, phi: f64, sigma: f64, delta: f64, v: f64) -> f64 {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SigmaError {
+    InvalidInput,
+    NoBracket,
+    NotFinite,
+    NoConvergence,
+}
+
+// how many steps each part of the volatility search took
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Iterations {
+    pub bracketing: u32,
+    pub convergence: u32,
+}
+
+impl SigmaError {
+    pub fn code(self) -> f64 {
+        match self {
+            SigmaError::InvalidInput => 1f64,
+            SigmaError::NoBracket => 2f64,
+            SigmaError::NotFinite => 3f64,
+            SigmaError::NoConvergence => 4f64,
+        }
+    }
+}
+
+pub fn get_sigma(
+    tau: f64,
+    phi: f64,
+    sigma: f64,
+    delta: f64,
+    v: f64,
+) -> (Result<f64, SigmaError>, Iterations) {
+    // returns the new volatility along with the number of iterations it took
+    // to bracket and then find it. each search gets its own cap. with a tau of
+    // 0, non-finite inputs or a function we can't bracket or converge on, we
+    // give up with an error rather than loop forever or hand back NaN
     const EPSILON: f64 = 0.000_000_01;
+    const MAX_BRACKETING: u32 = 100;
+    const MAX_ITERATIONS: u32 = 100;
+
+    let mut iterations = Iterations::default();
+    if !(tau > 0f64
+        && tau.is_finite()
+        && phi.is_finite()
+        && sigma.is_finite()
+        && delta.is_finite()
+        && v.is_finite()
+        && sigma > 0f64)
+    {
+        return (Err(SigmaError::InvalidInput), iterations);
+    }
 
     let alpha: f64 = (sigma.powi(2)).ln();
     let f = |x: f64| -> f64 {
@@ -72,6 +122,10 @@ pub fn get_sigma(tau: f64, phi: f64, sigma: f64, delta: f64, v: f64) -> f64 {
         let mut k: f64 = 1.0;
         while f(alpha - k * tau) < 0f64 {
             k += 1f64;
+            iterations.bracketing += 1;
+            if iterations.bracketing >= MAX_BRACKETING {
+                return (Err(SigmaError::NoBracket), iterations);
+            }
         }
         b = alpha - k * tau
     };
@@ -81,6 +135,13 @@ pub fn get_sigma(tau: f64, phi: f64, sigma: f64, delta: f64, v: f64) -> f64 {
     let mut fb: f64 = f(b);
     let mut fc: f64;
     while (b - a).abs() > EPSILON {
+        if !(fa.is_finite() && fb.is_finite()) {
+            return (Err(SigmaError::NotFinite), iterations);
+        }
+        if iterations.convergence >= MAX_ITERATIONS {
+            return (Err(SigmaError::NoConvergence), iterations);
+        }
+        iterations.convergence += 1;
         c = a + ((a - b) * fa / (fb - fa));
         fc = f(c);
         if fc * fb < 0f64 {
@@ -94,10 +155,201 @@ pub fn get_sigma(tau: f64, phi: f64, sigma: f64, delta: f64, v: f64) -> f64 {
         fb = fc;
     }
 
-    (a / 2f64).exp()
+    match (a / 2f64).exp() {
+        x if x.is_finite() => (Ok(x), iterations),
+        _ => (Err(SigmaError::NotFinite), iterations),
+    }
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5f64 * (1f64 + erf(x / 2f64.sqrt()))
+}
+
+pub fn normal_quantile(p: f64) -> f64 {
+    // Acklam's rational approximation of the inverse normal cdf, accurate to
+    // about 1e-9 on (0, 1)
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    if p <= 0f64 || p >= 1f64 || p.is_nan() {
+        return f64::NAN;
+    }
+    let tail = |q: f64| -> f64 {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1f64)
+    };
+    if p < P_LOW {
+        tail((-2f64 * p.ln()).sqrt())
+    } else if p > 1f64 - P_LOW {
+        -tail((-2f64 * (1f64 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5f64;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1f64)
+    }
+}
+
+pub fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let mut cov = 0f64;
+    let mut var_x = 0f64;
+    let mut var_y = 0f64;
+    for (a, b) in x.iter().zip(y.iter()) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+
+    cov / (var_x * var_y).sqrt()
+}
+
+pub fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    pearson(&ranks(x), &ranks(y))
+}
+
+pub fn kendall_tau(x: &[f64], y: &[f64]) -> f64 {
+    // tau-b, so pairs tied in either ordering don't count against it
+    let mut concordant = 0f64;
+    let mut discordant = 0f64;
+    let mut ties_x = 0f64;
+    let mut ties_y = 0f64;
+    for i in 0..x.len() {
+        for j in (i + 1)..x.len() {
+            let dx = x[i] - x[j];
+            let dy = y[i] - y[j];
+            if dx == 0f64 && dy == 0f64 {
+                continue;
+            } else if dx == 0f64 {
+                ties_x += 1f64;
+            } else if dy == 0f64 {
+                ties_y += 1f64;
+            } else if (dx > 0f64) == (dy > 0f64) {
+                concordant += 1f64;
+            } else {
+                discordant += 1f64;
+            }
+        }
+    }
+    let pairs_x = concordant + discordant + ties_y;
+    let pairs_y = concordant + discordant + ties_x;
+
+    (concordant - discordant) / (pairs_x * pairs_y).sqrt()
+}
+
+pub fn ks_statistic(sorted_x: &[f64], sorted_y: &[f64]) -> f64 {
+    // the largest gap between the two empirical cdfs
+    let n_x = sorted_x.len() as f64;
+    let n_y = sorted_y.len() as f64;
+    let (mut i, mut j) = (0, 0);
+    let mut d = 0f64;
+    while i < sorted_x.len() && j < sorted_y.len() {
+        let value = sorted_x[i].min(sorted_y[j]);
+        while i < sorted_x.len() && sorted_x[i] <= value {
+            i += 1;
+        }
+        while j < sorted_y.len() && sorted_y[j] <= value {
+            j += 1;
+        }
+        d = d.max((i as f64 / n_x - j as f64 / n_y).abs());
+    }
+
+    d
+}
+
+pub fn ks_p_value(d: f64, n_x: usize, n_y: usize) -> f64 {
+    // the asymptotic kolmogorov distribution, with Stephens' correction for
+    // small samples
+    let n = (n_x * n_y) as f64 / (n_x + n_y) as f64;
+    let lambda = (n.sqrt() + 0.12 + 0.11 / n.sqrt()) * d;
+    if lambda < 1e-3 {
+        return 1f64;
+    }
+    let mut p = 0f64;
+    for k in 1..=100 {
+        let term = 2f64 * (-2f64 * (k as f64 * lambda).powi(2)).exp();
+        p += if k % 2 == 1 { term } else { -term };
+        if term < 1e-12 {
+            break;
+        }
+    }
+
+    p.clamp(0f64, 1f64)
+}
+
+fn ranks(x: &[f64]) -> Vec<f64> {
+    // 1-based ranks, ties get the average of the ranks they span
+    let mut order: Vec<usize> = (0..x.len()).collect();
+    order.sort_by(|&a, &b| x[a].partial_cmp(&x[b]).unwrap_or(Equal));
+    let mut ranks = vec![0f64; x.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && x[order[j + 1]] == x[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2f64 + 1f64;
+        for &k in &order[i..=j] {
+            ranks[k] = rank;
+        }
+        i = j + 1;
+    }
+
+    ranks
+}
+
+fn erf(x: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26, accurate to about 1.5e-7
+    const P: f64 = 0.327_591_1;
+    const A: [f64; 5] = [
+        0.254_829_592,
+        -0.284_496_736,
+        1.421_413_741,
+        -1.453_152_027,
+        1.061_405_429,
+    ];
+
+    let t = 1f64 / (1f64 + P * x.abs());
+    let poly = ((((A[4] * t + A[3]) * t + A[2]) * t + A[1]) * t + A[0]) * t;
+    let y = 1f64 - poly * (-x * x).exp();
+    if x < 0f64 {
+        -y
+    } else {
+        y
+    }
 }
 
-fn percentile_of(sorted_times: &[f64], pct: f64) -> f64 {
+pub fn percentile_of(sorted_times: &[f64], pct: f64) -> f64 {
     if sorted_times.len() == 1 {
         return sorted_times[0];
     }
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::wrap_pyfunction;
+
+use crate::glicko::MultiPeriod;
+#[cfg(feature = "python")]
+use crate::Error;
+use crate::{math, Result};
+
+#[cfg(feature = "python")]
+pub fn add_functions(m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pyfunction!(simulate))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+/// Rate a season of races between players with known skills.
+///
+/// Returns a dict for each period with how well the ratings match the
+/// true skills.
+#[pyfunction(
+    players = "200",
+    periods = "4",
+    races = "100",
+    min_size = "2",
+    max_size = "12",
+    forfeit_rate = "0.08",
+    seed = "0",
+    constants = "None"
+)]
+#[allow(clippy::too_many_arguments)]
+#[text_signature = "(players=200, periods=4, races=100, min_size=2, max_size=12, forfeit_rate=0.08, seed=0, constants=None)"]
+fn simulate(
+    players: usize,
+    periods: usize,
+    races: usize,
+    min_size: usize,
+    max_size: usize,
+    forfeit_rate: f64,
+    seed: u64,
+    constants: Option<HashMap<&str, f64>>,
+) -> PyResult<Vec<HashMap<&'static str, f64>>> {
+    validate_simulation(players, periods, min_size, max_size, forfeit_rate)?;
+    let mut simulation = Simulation::new(players, min_size, max_size, forfeit_rate, seed);
+
+    Ok(simulation.run(periods, races, constants.as_ref())?)
+}
+
+// a small xorshift generator. seeded runs have to give the same season on
+// every platform, and we don't need anything stronger
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck at zero, so spread the seed out and keep it odd
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn uniform(&mut self) -> f64 {
+        // 53 random bits in [0, 1)
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn normal(&mut self) -> f64 {
+        // box-muller, discarding the second value
+        let u = self.uniform().max(f64::MIN_POSITIVE);
+        let v = self.uniform();
+        (-2f64 * u.ln()).sqrt() * (2f64 * std::f64::consts::PI * v).cos()
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+pub struct Simulation {
+    rng: Rng,
+    names: Vec<String>,
+    skill: Vec<f64>,
+    activity: Vec<f64>,
+    min_size: usize,
+    max_size: usize,
+    forfeit_rate: f64,
+}
+
+impl Simulation {
+    pub fn new(
+        players: usize,
+        min_size: usize,
+        max_size: usize,
+        forfeit_rate: f64,
+        seed: u64,
+    ) -> Simulation {
+        // every player gets a hidden true skill from a standard normal and
+        // an activity level. some players enter far more races than others
+        let mut rng = Rng::new(seed);
+        let names: Vec<String> = (0..players).map(|i| format!("player {}", i)).collect();
+        let skill: Vec<f64> = (0..players).map(|_| rng.normal()).collect();
+        let activity: Vec<f64> = (0..players).map(|_| (0.75 * rng.normal()).exp()).collect();
+
+        Simulation {
+            rng,
+            names,
+            skill,
+            activity,
+            min_size,
+            max_size,
+            forfeit_rate,
+        }
+    }
+
+    pub fn race(&mut self) -> HashMap<String, f64> {
+        // most races are small and a few are much bigger
+        let spread = (self.max_size - self.min_size + 1) as f64;
+        let size = self.min_size + (spread * self.rng.uniform().powi(2)) as usize;
+        let max_activity = self.activity.iter().cloned().fold(0f64, f64::max);
+        let mut entrants: Vec<usize> = Vec::with_capacity(size);
+        while entrants.len() < size {
+            let i = self.rng.below(self.names.len());
+            if !entrants.contains(&i) && self.rng.uniform() < self.activity[i] / max_activity {
+                entrants.push(i);
+            }
+        }
+
+        // times are log-normal around a seed length that varies from race to
+        // race. better players finish faster and forfeit less often
+        let seed_length = 5400f64 * (0.1 * self.rng.normal()).exp();
+        let mut times: Vec<(usize, f64)> = Vec::with_capacity(size);
+        for &i in entrants.iter() {
+            let time = seed_length * (-0.08 * self.skill[i] + 0.05 * self.rng.normal()).exp();
+            let forfeit_chance = (self.forfeit_rate * (-0.5 * self.skill[i]).exp()).min(0.5);
+            if self.rng.uniform() < forfeit_chance {
+                times.push((i, f64::NAN));
+            } else {
+                times.push((i, time.round()));
+            }
+        }
+        if times.iter().all(|(_, t)| t.is_nan()) {
+            // a race needs a finisher, so the best player in it gets one
+            let best = entrants.iter().cloned().fold(entrants[0], |a, b| {
+                if self.skill[b] > self.skill[a] {
+                    b
+                } else {
+                    a
+                }
+            });
+            let time = seed_length * (-0.08 * self.skill[best]).exp();
+            times.retain(|(i, _)| *i != best);
+            times.push((best, time.round()));
+        }
+
+        times
+            .into_iter()
+            .map(|(i, t)| (self.names[i].clone(), t))
+            .collect()
+    }
+
+    pub fn run(
+        &mut self,
+        periods: usize,
+        races: usize,
+        constants: Option<&HashMap<&str, f64>>,
+    ) -> Result<Vec<HashMap<&'static str, f64>>> {
+        // feed the generated races through one MultiPeriod per period,
+        // carrying everyone's ratings over, and compare the ratings with the
+        // true skills after each one
+        let mut ratings: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        let mut reports: Vec<HashMap<&'static str, f64>> = Vec::with_capacity(periods);
+        for period_num in 0..periods {
+            let mut period = MultiPeriod::default();
+            if let Some(constants) = constants {
+                period.set_constant_map(constants.clone())?;
+            }
+            period.add_player_map(ratings)?;
+            let period_races: Vec<HashMap<String, f64>> = (0..races).map(|_| self.race()).collect();
+            period.add_race_list(period_races, "weekly", None)?;
+            ratings = period
+                .rankings(true)
+                .into_iter()
+                .map(|(name, player)| {
+                    let player = player
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), v))
+                        .collect();
+                    (name.to_string(), player)
+                })
+                .collect();
+            reports.push(self.report(&ratings, period_num + 1, races));
+        }
+
+        Ok(reports)
+    }
+
+    fn report(
+        &self,
+        ratings: &HashMap<String, HashMap<String, f64>>,
+        periods: usize,
+        races: usize,
+    ) -> HashMap<&'static str, f64> {
+        let index: HashMap<&str, usize> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let mut rating: Vec<f64> = Vec::with_capacity(ratings.len());
+        let mut skill: Vec<f64> = Vec::with_capacity(ratings.len());
+        for (name, player) in ratings.iter() {
+            rating.push(player["rating"]);
+            skill.push(self.skill[index[name.as_str()]]);
+        }
+        let mut report: HashMap<&'static str, f64> = HashMap::with_capacity(4);
+        report.insert("players", rating.len() as f64);
+        report.insert("races", (periods * races) as f64);
+        report.insert("pearson", math::pearson(&rating, &skill));
+        report.insert("spearman", math::spearman(&rating, &skill));
+
+        report
+    }
+}
+
+#[cfg(feature = "python")]
+fn validate_simulation(
+    players: usize,
+    periods: usize,
+    min_size: usize,
+    max_size: usize,
+    forfeit_rate: f64,
+) -> Result<()> {
+    if periods < 1 {
+        return Err(Error::new("Must simulate at least one period"));
+    }
+    if min_size < 2 || max_size < min_size || max_size > players {
+        return Err(Error::new(
+            "Race sizes must be at least two and no more than the number of players",
+        ));
+    }
+    if !(0f64..1f64).contains(&forfeit_rate) {
+        return Err(Error::new(
+            "Forfeit rate must be at least 0 and less than 1",
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+
+use crate::{math, GlickoError};
+
+pub fn add_functions(m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pyfunction!(rating_interval))?;
+    m.add_wrapped(wrap_pyfunction!(probability_stronger))?;
+    m.add_wrapped(wrap_pyfunction!(distinguishable))?;
+    m.add_wrapped(wrap_pyfunction!(distribution))?;
+
+    Ok(())
+}
+
+/// The range a player's true rating is in with the given confidence.
+#[pyfunction(level = "0.95")]
+#[text_signature = "(player, level=0.95)"]
+fn rating_interval(player: HashMap<String, f64>, level: f64) -> PyResult<(f64, f64)> {
+    validate_player(&player)?;
+    validate_level(level)?;
+    let z = math::normal_quantile((1f64 + level) / 2f64);
+    let margin = z * player["deviation"];
+
+    Ok((player["rating"] - margin, player["rating"] + margin))
+}
+
+/// The probability that player's true rating is higher than opponent's.
+#[pyfunction]
+#[text_signature = "(player, opponent)"]
+fn probability_stronger(
+    player: HashMap<String, f64>,
+    opponent: HashMap<String, f64>,
+) -> PyResult<f64> {
+    // the probability that the player's true rating is above the opponent's,
+    // treating both ratings as independent normal distributions
+    validate_player(&player)?;
+    validate_player(&opponent)?;
+    let diff = player["rating"] - opponent["rating"];
+    let spread = (player["deviation"].powi(2) + opponent["deviation"].powi(2)).sqrt();
+
+    Ok(math::normal_cdf(diff / spread))
+}
+
+/// Whether we're confident which of two players is stronger.
+#[pyfunction(level = "0.95")]
+#[text_signature = "(player, opponent, level=0.95)"]
+fn distinguishable(
+    player: HashMap<String, f64>,
+    opponent: HashMap<String, f64>,
+    level: f64,
+) -> PyResult<bool> {
+    validate_level(level)?;
+    let p = probability_stronger(player, opponent)?;
+
+    Ok(p >= level || p <= 1f64 - level)
+}
+
+/// Summary statistics for one key of a period's rankings.
+///
+/// With a list of reference values, also compares the two with a
+/// Kolmogorov-Smirnov test.
+#[pyfunction(reference = "None", key = "\"rating\"")]
+#[text_signature = "(rankings, reference=None, key='rating')"]
+fn distribution(
+    rankings: HashMap<String, HashMap<String, f64>>,
+    reference: Option<Vec<f64>>,
+    key: &str,
+) -> PyResult<HashMap<&'static str, f64>> {
+    // summarize the shape of a period's ratings so it can be checked against
+    // what we expect, or against a known good period
+    let mut values: Vec<f64> = Vec::with_capacity(rankings.len());
+    for player in rankings.values() {
+        match player.get(key) {
+            Some(x) if x.is_finite() => values.push(*x),
+            _ => {
+                return Err(GlickoError::py_err(format!(
+                    "Every player must have a finite {}",
+                    key
+                )))
+            }
+        }
+    }
+    if values.len() < 4 {
+        return Err(GlickoError::py_err(
+            "Need at least four players to describe a distribution",
+        ));
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let moment = |k: i32| values.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / n;
+    let (m2, m3, m4) = (moment(2), moment(3), moment(4));
+    let skewness = m3 / m2.powf(1.5);
+    let kurtosis = m4 / m2.powi(2) - 3f64;
+    // jarque-bera is chi-squared with two degrees of freedom for normal data
+    let jarque_bera = n / 6f64 * (skewness.powi(2) + kurtosis.powi(2) / 4f64);
+
+    let mut distribution_dict: HashMap<&str, f64> = HashMap::with_capacity(14);
+    distribution_dict.insert("count", n);
+    distribution_dict.insert("mean", mean);
+    distribution_dict.insert("stddev", (m2 * n / (n - 1f64)).sqrt());
+    distribution_dict.insert("skewness", skewness);
+    distribution_dict.insert("kurtosis", kurtosis);
+    distribution_dict.insert("min", values[0]);
+    distribution_dict.insert("max", values[values.len() - 1]);
+    for (name, pct) in [
+        ("p5", 5f64),
+        ("p25", 25f64),
+        ("p50", 50f64),
+        ("p75", 75f64),
+        ("p95", 95f64),
+    ]
+    .iter()
+    {
+        distribution_dict.insert(name, math::percentile_of(&values, *pct));
+    }
+    distribution_dict.insert("jarque_bera", jarque_bera);
+    distribution_dict.insert("jarque_bera_p", (-jarque_bera / 2f64).exp());
+
+    if let Some(mut reference) = reference {
+        if reference.is_empty() || reference.iter().any(|x| !x.is_finite()) {
+            return Err(GlickoError::py_err(
+                "Reference distribution must be a non-empty list of finite values",
+            ));
+        }
+        reference.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let d = math::ks_statistic(&values, &reference);
+        distribution_dict.insert("ks", d);
+        distribution_dict.insert("ks_p", math::ks_p_value(d, values.len(), reference.len()));
+    }
+
+    Ok(distribution_dict)
+}
+
+fn validate_player(player: &HashMap<String, f64>) -> PyResult<()> {
+    if !(player.contains_key("rating") && player.contains_key("deviation")) {
+        return Err(GlickoError::py_err(
+            "Player dict must have a rating and deviation",
+        ));
+    }
+    if player["deviation"] <= 0f64 {
+        return Err(GlickoError::py_err("Player deviation must be positive"));
+    }
+
+    Ok(())
+}
+
+fn validate_level(level: f64) -> PyResult<()> {
+    if !(level > 0f64 && level < 1f64) {
+        return Err(GlickoError::py_err(
+            "Confidence level must be between 0 and 1",
+        ));
+    }
+
+    Ok(())
+}
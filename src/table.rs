@@ -0,0 +1,409 @@
+use std::collections::{BTreeSet, HashMap};
+
+use pyo3::buffer::PyBuffer;
+use pyo3::prelude::*;
+use pyo3::types::{PyAny, PyDict, PyList};
+use pyo3::PyNativeType;
+
+use crate::GlickoError;
+
+// races and rankings as columns instead of nested dicts. a table of races is
+// in long format with one row per runner per race: race_id, player and time,
+// and optionally a timestamp for each row's race
+
+pub type Races = (Vec<HashMap<String, f64>>, Option<Vec<f64>>);
+
+// a table of races, read a column at a time. arrow and pandas columns are
+// read through numpy and a dict of lists element by element
+pub enum Table<'a> {
+    Arrow(&'a PyAny),
+    Pandas(&'a PyAny),
+    Lists(&'a PyDict),
+}
+
+pub fn columns(obj: &PyAny) -> PyResult<Option<Table<'_>>> {
+    // the table behind obj, or none if it isn't one. polars dataframes
+    // convert to arrow without copying. a dict is only a table if every
+    // value is a list, since a single race is also a dict
+    if let Ok(dict) = obj.downcast_ref::<PyDict>() {
+        let lists = !dict.is_empty()
+            && dict
+                .values()
+                .iter()
+                .all(|x| x.downcast_ref::<PyList>().is_ok());
+        return Ok(if lists {
+            Some(Table::Lists(dict))
+        } else {
+            None
+        });
+    }
+    if obj.hasattr("column_names")? && obj.hasattr("column")? {
+        Ok(Some(Table::Arrow(obj)))
+    } else if obj.hasattr("to_arrow")? {
+        Ok(Some(Table::Arrow(obj.call_method0("to_arrow")?)))
+    } else if obj.hasattr("to_numpy")? && obj.hasattr("columns")? {
+        Ok(Some(Table::Pandas(obj)))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn races(table: &Table) -> PyResult<Races> {
+    // rows are grouped into races by race_id, in the order each race first
+    // appears. missing times are forfeits
+    let column = |name: &str| {
+        table
+            .column(name)?
+            .ok_or_else(|| GlickoError::py_err(format!("Race table has no {} column", name)))
+    };
+    let race_ids = race_ids(column("race_id")?)?;
+    let players = strings("player", column("player")?)?;
+    let times = numbers("time", column("time")?)?;
+    let timestamps = match table.column("timestamp")? {
+        Some(x) => Some(numbers("timestamp", x)?),
+        None => None,
+    };
+    if players.len() != race_ids.len()
+        || times.len() != race_ids.len()
+        || timestamps
+            .as_ref()
+            .is_some_and(|x| x.len() != race_ids.len())
+    {
+        return Err(GlickoError::py_err("Race table columns differ in length"));
+    }
+
+    let mut index: HashMap<&RaceId, usize> = HashMap::new();
+    let mut races: Vec<HashMap<String, f64>> = Vec::new();
+    let mut race_timestamps: Vec<f64> = Vec::new();
+    for (row, race_id) in race_ids.iter().enumerate() {
+        let i = *index.entry(race_id).or_insert_with(|| {
+            races.push(HashMap::new());
+            race_timestamps.push(timestamps.as_ref().map_or(f64::NAN, |x| x[row]));
+            races.len() - 1
+        });
+        if races[i].insert(players[row].clone(), times[row]).is_some() {
+            return Err(GlickoError::py_err(format!(
+                "{} appears twice in the same race",
+                players[row]
+            )));
+        }
+    }
+
+    Ok((races, timestamps.map(|_| race_timestamps)))
+}
+
+impl<'a> Table<'a> {
+    fn column(&self, name: &str) -> PyResult<Option<Vec<Column>>> {
+        // a column as its chunks, or none if the table doesn't have it
+        match *self {
+            Table::Arrow(obj) => {
+                let names: Vec<String> = obj.getattr("column_names")?.extract()?;
+                if !names.iter().any(|x| x == name) {
+                    return Ok(None);
+                }
+                // tables have chunked columns and record batches don't
+                let column = obj.call_method1("column", (name,))?;
+                let chunks: Vec<&PyAny> = match column.hasattr("chunks")? {
+                    true => column.getattr("chunks")?.extract()?,
+                    false => vec![column],
+                };
+                let chunks: PyResult<Vec<Column>> = chunks
+                    .into_iter()
+                    .map(|x| {
+                        let kwargs = PyDict::new(x.py());
+                        kwargs.set_item("zero_copy_only", false)?;
+                        numpy_column(x.call_method("to_numpy", (), Some(kwargs))?)
+                    })
+                    .collect();
+                Ok(Some(chunks?))
+            }
+            Table::Pandas(obj) => {
+                let names: Vec<String> =
+                    obj.getattr("columns")?.call_method0("tolist")?.extract()?;
+                if !names.iter().any(|x| x == name) {
+                    return Ok(None);
+                }
+                // nullable columns like Int64 would otherwise have pd.NA
+                let kwargs = PyDict::new(obj.py());
+                kwargs.set_item("na_value", obj.py().None())?;
+                let array = obj
+                    .get_item(name)?
+                    .call_method("to_numpy", (), Some(kwargs))?;
+                Ok(Some(vec![numpy_column(array)?]))
+            }
+            Table::Lists(dict) => match dict.get_item(name) {
+                Some(x) => Ok(Some(vec![list_column(x)?])),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+// one chunk of a column. integers and floats are kept apart so race ids can
+// be numbers, and none is a null
+enum Column {
+    Integers(Vec<Option<i64>>),
+    Floats(Vec<Option<f64>>),
+    Strings(Vec<Option<String>>),
+}
+
+fn race_ids(chunks: Vec<Column>) -> PyResult<Vec<RaceId>> {
+    let mut race_ids: Vec<RaceId> = Vec::new();
+    for chunk in chunks {
+        match chunk {
+            Column::Integers(x) => {
+                for id in x {
+                    race_ids.push(RaceId::Number(id.ok_or_else(|| missing("race_id"))?));
+                }
+            }
+            Column::Strings(x) => {
+                for id in x {
+                    race_ids.push(RaceId::Name(id.ok_or_else(|| missing("race_id"))?));
+                }
+            }
+            Column::Floats(_) => {
+                return Err(GlickoError::py_err(
+                    "The race_id column must be integers or strings",
+                ))
+            }
+        }
+    }
+
+    Ok(race_ids)
+}
+
+fn strings(name: &str, chunks: Vec<Column>) -> PyResult<Vec<String>> {
+    let mut strings: Vec<String> = Vec::new();
+    for chunk in chunks {
+        match chunk {
+            Column::Strings(x) => {
+                for value in x {
+                    strings.push(value.ok_or_else(|| missing(name))?);
+                }
+            }
+            _ => {
+                return Err(GlickoError::py_err(format!(
+                    "The {} column must be strings",
+                    name
+                )))
+            }
+        }
+    }
+
+    Ok(strings)
+}
+
+fn numbers(name: &str, chunks: Vec<Column>) -> PyResult<Vec<f64>> {
+    // nulls are NaN
+    let mut numbers: Vec<f64> = Vec::new();
+    for chunk in chunks {
+        match chunk {
+            Column::Integers(x) => {
+                numbers.extend(x.into_iter().map(|x| x.map_or(f64::NAN, |x| x as f64)))
+            }
+            Column::Floats(x) => numbers.extend(x.into_iter().map(|x| x.unwrap_or(f64::NAN))),
+            Column::Strings(_) => {
+                return Err(GlickoError::py_err(format!(
+                    "The {} column must be numbers",
+                    name
+                )))
+            }
+        }
+    }
+
+    Ok(numbers)
+}
+
+fn missing(name: &str) -> PyErr {
+    GlickoError::py_err(format!("The {} column has missing values", name))
+}
+
+fn list_column(obj: &PyAny) -> PyResult<Column> {
+    if let Ok(x) = obj.extract() {
+        return Ok(Column::Integers(x));
+    }
+    if let Ok(x) = obj.extract() {
+        return Ok(Column::Floats(x));
+    }
+    match obj.extract() {
+        Ok(x) => Ok(Column::Strings(x)),
+        Err(_) => Err(GlickoError::py_err(
+            "Race table columns must be all numbers or all strings",
+        )),
+    }
+}
+
+fn numpy_column(array: &PyAny) -> PyResult<Column> {
+    // numeric columns are read from the array's buffer. anything else, like
+    // pandas' object columns of strings, is a list of python objects anyway
+    let py = array.py();
+    if array.hasattr("dtype")? {
+        let kind: String = array.getattr("dtype")?.getattr("kind")?.extract()?;
+        if kind == "M" {
+            return datetime_column(array);
+        }
+    }
+    if let Ok(buffer) = PyBuffer::get(py, array) {
+        if let Ok(x) = buffer.to_vec::<f64>(py) {
+            return Ok(Column::Floats(x.into_iter().map(Some).collect()));
+        }
+        if let Ok(x) = buffer.to_vec::<i64>(py) {
+            return Ok(Column::Integers(x.into_iter().map(Some).collect()));
+        }
+        if let Ok(x) = buffer.to_vec::<i32>(py) {
+            return Ok(Column::Integers(
+                x.into_iter().map(|x| Some(x.into())).collect(),
+            ));
+        }
+    }
+
+    list_column(array.call_method0("tolist")?)
+}
+
+fn datetime_column(array: &PyAny) -> PyResult<Column> {
+    // datetime64 columns become seconds since the epoch, whatever their
+    // unit. NaT is the smallest int64
+    let py = array.py();
+    let nanoseconds = array
+        .call_method1("astype", ("datetime64[ns]",))?
+        .call_method1("view", ("int64",))?;
+    let nanoseconds = PyBuffer::get(py, nanoseconds)?.to_vec::<i64>(py)?;
+
+    Ok(Column::Floats(
+        nanoseconds
+            .into_iter()
+            .map(|x| match x {
+                i64::MIN => None,
+                _ => Some(x as f64 / 1e9),
+            })
+            .collect(),
+    ))
+}
+
+pub fn race_arrays(
+    py: Python,
+    names: &[String],
+    players: &PyAny,
+    offsets: &PyAny,
+    times: &PyAny,
+) -> PyResult<Vec<HashMap<String, f64>>> {
+    // races from flat arrays instead of dicts. players holds an index into
+    // names for each row and times the matching times, with race i being
+    // rows offsets[i] up to offsets[i + 1]
+    let players = indices(py, players)?;
+    let offsets = indices(py, offsets)?;
+    let times = floats(py, times)?;
+    if times.len() != players.len() {
+        return Err(GlickoError::py_err(
+            "Player and time arrays differ in length",
+        ));
+    }
+    if offsets.first() != Some(&0)
+        || offsets.last() != Some(&players.len())
+        || offsets.windows(2).any(|x| x[0] > x[1])
+    {
+        return Err(GlickoError::py_err(
+            "Race offsets must start at 0, never decrease and end at the number of rows",
+        ));
+    }
+    if let Some(x) = players.iter().find(|&&x| x >= names.len()) {
+        return Err(GlickoError::py_err(format!("No player with index {}", x)));
+    }
+
+    offsets
+        .windows(2)
+        .map(|x| {
+            let mut race: HashMap<String, f64> = HashMap::with_capacity(x[1] - x[0]);
+            for row in x[0]..x[1] {
+                if race
+                    .insert(names[players[row]].clone(), times[row])
+                    .is_some()
+                {
+                    return Err(GlickoError::py_err(format!(
+                        "{} appears twice in the same race",
+                        names[players[row]]
+                    )));
+                }
+            }
+            Ok(race)
+        })
+        .collect()
+}
+
+pub fn floats(py: Python, obj: &PyAny) -> PyResult<Vec<f64>> {
+    let buffer = vector(py, obj)?;
+    match buffer.to_vec::<f64>(py) {
+        Ok(x) => Ok(x),
+        Err(_) => Ok(buffer
+            .to_vec::<f32>(py)
+            .map_err(|_| GlickoError::py_err("Expected an array of floats"))?
+            .into_iter()
+            .map(f64::from)
+            .collect()),
+    }
+}
+
+fn indices(py: Python, obj: &PyAny) -> PyResult<Vec<usize>> {
+    // numpy's default integers are 32 bits on some platforms
+    let buffer = vector(py, obj)?;
+    let signed: Vec<i64> = match buffer.to_vec::<i64>(py) {
+        Ok(x) => x,
+        Err(_) => match buffer.to_vec::<i32>(py) {
+            Ok(x) => x.into_iter().map(i64::from).collect(),
+            Err(_) => match buffer.to_vec::<u32>(py) {
+                Ok(x) => x.into_iter().map(i64::from).collect(),
+                Err(_) => return Err(GlickoError::py_err("Expected an array of integers")),
+            },
+        },
+    };
+    if signed.iter().any(|&x| x < 0) {
+        return Err(GlickoError::py_err("Indices can't be negative"));
+    }
+
+    Ok(signed.into_iter().map(|x| x as usize).collect())
+}
+
+fn vector(py: Python, obj: &PyAny) -> PyResult<PyBuffer> {
+    let buffer = PyBuffer::get(py, obj)?;
+    if buffer.dimensions() != 1 {
+        return Err(GlickoError::py_err("Expected a one dimensional array"));
+    }
+
+    Ok(buffer)
+}
+
+pub fn record_batch(
+    py: Python,
+    rankings: &HashMap<String, HashMap<&str, f64>>,
+) -> PyResult<PyObject> {
+    // one row per player, sorted by name, and one column for each key in
+    // the rankings dicts. a key some players don't have is NaN for them
+    let mut names: Vec<&str> = rankings.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    let keys: BTreeSet<&str> = rankings.values().flat_map(|x| x.keys().cloned()).collect();
+    let pyarrow = py.import("pyarrow")?;
+    let float64 = pyarrow.call_method0("float64")?;
+    let mut fields: Vec<&str> = vec!["player"];
+    let mut arrays: Vec<&PyAny> =
+        vec![pyarrow.call_method1("array", (names.clone(), pyarrow.call_method0("string")?))?];
+    for key in keys {
+        let values: Vec<f64> = names
+            .iter()
+            .map(|&x| *rankings[x].get(key).unwrap_or(&f64::NAN))
+            .collect();
+        arrays.push(pyarrow.call_method1("array", (values, float64))?);
+        fields.push(key);
+    }
+    let batch = pyarrow
+        .getattr("RecordBatch")?
+        .call_method1("from_arrays", (arrays, fields))?;
+
+    Ok(batch.to_object(py))
+}
+
+// race ids can be numbers or names
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum RaceId {
+    Number(i64),
+    Name(String),
+}
@@ -0,0 +1,116 @@
+use wasm_bindgen::prelude::*;
+
+use crate::glicko::MultiPeriod;
+use crate::{json, Error};
+
+// the same rating period as the python bindings, for javascript. everything
+// goes in and comes out as json strings, with null for forfeits, and errors
+// are thrown as their messages
+
+/// A rating period. Add players and races, then rank them.
+#[wasm_bindgen(js_name = MultiPeriod)]
+pub struct Period {
+    period: MultiPeriod,
+}
+
+#[wasm_bindgen(js_class = MultiPeriod)]
+impl Period {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Period {
+        Period {
+            period: MultiPeriod::default(),
+        }
+    }
+
+    /// Create a period using one of the built-in presets, "alttpr" or "glicko2".
+    #[wasm_bindgen(js_name = fromPreset)]
+    pub fn from_preset(name: &str) -> Result<Period, JsValue> {
+        let period = MultiPeriod::preset(name).map_err(js_error)?;
+
+        Ok(Period { period })
+    }
+
+    /// Set several constants at once from an object of constant names to values.
+    #[wasm_bindgen(js_name = setConstants)]
+    pub fn set_constants(&mut self, constants: &str) -> Result<(), JsValue> {
+        let constants = json::constants(constants).map_err(js_error)?;
+        self.period
+            .set_constant_map(constants.iter().map(|(k, v)| (k.as_str(), *v)).collect())
+            .map_err(js_error)
+    }
+
+    /// Add players with ratings from an earlier period, as returned by rank().
+    #[wasm_bindgen(js_name = addPlayers)]
+    pub fn add_players(&mut self, players: &str) -> Result<(), JsValue> {
+        let players = json::players(players).map_err(js_error)?;
+        self.period.add_player_map(players).map_err(js_error)
+    }
+
+    /// Add a race or a list of races and return their IDs.
+    ///
+    /// Each race is an object of names to times in seconds, with null for
+    /// forfeits. timestamps is optionally a list of each race's unix time.
+    #[wasm_bindgen(js_name = addRaces)]
+    pub fn add_races(
+        &mut self,
+        races: &str,
+        event: Option<String>,
+        timestamps: Option<String>,
+    ) -> Result<String, JsValue> {
+        let races = json::races(races).map_err(js_error)?;
+        let timestamps = json::timestamps(timestamps.as_deref().unwrap_or("")).map_err(js_error)?;
+        let ids = self
+            .period
+            .add_race_list(races, event.as_deref().unwrap_or("weekly"), timestamps)
+            .map_err(js_error)?;
+
+        Ok(json::ids(&ids))
+    }
+
+    /// Rank every player and return an object of names to their ratings.
+    pub fn rank(&self, end: Option<bool>) -> String {
+        json::to_string(&self.period.rankings(end.unwrap_or(true)))
+    }
+
+    /// Add races and return the current ratings of everyone in them.
+    pub fn update(&mut self, races: &str, event: Option<String>) -> Result<String, JsValue> {
+        let races = json::races(races).map_err(js_error)?;
+        let current = self
+            .period
+            .update_races(races, event.as_deref().unwrap_or("weekly"), None)
+            .map_err(js_error)?;
+
+        Ok(json::to_string(&current))
+    }
+
+    /// The ratings everyone in a race would have if it were added, without
+    /// adding it.
+    ///
+    /// For "what if I place Nth" previews, the period is left as it was.
+    pub fn preview(&self, races: &str, event: Option<String>) -> Result<String, JsValue> {
+        let races = json::races(races).map_err(js_error)?;
+        let current = self
+            .period
+            .clone()
+            .update_races(races, event.as_deref().unwrap_or("weekly"), None)
+            .map_err(js_error)?;
+
+        Ok(json::to_string(&current))
+    }
+
+    /// Close the period in place and start the next one with no races.
+    #[wasm_bindgen(js_name = nextPeriod)]
+    pub fn next_period(&mut self) {
+        self.period.close();
+    }
+}
+
+impl Default for Period {
+    fn default() -> Self {
+        Period::new()
+    }
+}
+
+fn js_error(e: Error) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
@@ -1,4 +1,7 @@
+import ast
+import inspect
 import math
+import os
 
 # TODO: automate debug building + test
 import pytest
@@ -53,3 +56,754 @@ def test_adding_races():
         test_period.add_races([good_race, bad_race_1])
     with pytest.raises(GlickoError):
         test_period.add_races([good_race, bad_race_2])
+
+def test_seeding_new_players():
+    race = {'seeded': 1500,
+            'unseeded': 1500}
+    seeds = {'seeded': [{'rating': 1900, 'deviation': 60},
+                        {'rating': 1800, 'deviation': 80}]}
+
+    unseeded_period = rr.MultiPeriod()
+    unseeded_period.add_seeds(seeds)
+    unseeded_period.add_races([race])
+    unseeded_rankings = unseeded_period.rank()
+
+    assert unseeded_rankings['seeded']['rating'] == unseeded_rankings['unseeded']['rating']
+
+    seeded_period = rr.MultiPeriod()
+    seeded_period.set_seed_correlation(.6)
+    seeded_period.add_seeds(seeds)
+    seeded_period.add_races([race])
+    seeded_rankings = seeded_period.rank()
+
+    assert seeded_rankings['seeded']['rating'] > seeded_rankings['unseeded']['rating']
+    assert seeded_rankings['seeded']['deviation'] < seeded_rankings['unseeded']['deviation']
+
+    with pytest.raises(GlickoError):
+        seeded_period.set_seed_correlation(1.5)
+
+def test_event_weights():
+    race = {'winner': 1500,
+            'loser': 1600}
+
+    casual_period = rr.MultiPeriod()
+    casual_period.add_races([race], event='casual')
+    casual_rankings = casual_period.rank()
+
+    tournament_period = rr.MultiPeriod()
+    tournament_period.add_races([race], event='tournament')
+    tournament_rankings = tournament_period.rank()
+
+    assert tournament_rankings['winner']['rating'] > casual_rankings['winner']['rating']
+    assert tournament_rankings['loser']['rating'] < casual_rankings['loser']['rating']
+
+    with pytest.raises(GlickoError):
+        tournament_period.add_races([race], event='exhibition')
+    with pytest.raises(GlickoError):
+        tournament_period.set_event_weight('casual', 0)
+
+def test_async_races():
+    day = 86400
+    async_race = {'early': (1500, 0),
+                  'on_time': (1450, 2 * day),
+                  'late': (1400, 5 * day)}
+
+    open_period = rr.MultiPeriod()
+    open_period.add_async_races([async_race])
+    open_rankings = open_period.rank()
+
+    assert open_rankings['late']['rating'] > open_rankings['on_time']['rating']
+
+    deadline_period = rr.MultiPeriod()
+    deadline_period.set_async_window(3 * day)
+    deadline_period.add_async_races([async_race])
+    deadline_rankings = deadline_period.rank()
+
+    assert deadline_rankings['on_time']['rating'] > deadline_rankings['early']['rating']
+    assert deadline_rankings['early']['rating'] > deadline_rankings['late']['rating']
+
+    with pytest.raises(GlickoError):
+        deadline_period.add_async_races([{'early': (1500, 0), 'missing': (1600, math.nan)}])
+    with pytest.raises(GlickoError):
+        deadline_period.add_races([{'a': 1500, 'b': 1600}], event='async')
+
+def test_removing_and_updating_races():
+    test_period = rr.MultiPeriod()
+    first_id, second_id = test_period.add_races([{'a': 1500, 'b': 1600},
+                                                 {'a': 1500, 'c': 1400}])
+    unchanged_rankings = test_period.rank()
+
+    test_period.remove_race(second_id)
+
+    assert 'c' not in test_period.players
+    assert list(test_period.races.keys()) == [first_id]
+
+    test_period.update_race(first_id, {'a': 1700, 'b': 1600})
+    updated_rankings = test_period.rank()
+
+    assert updated_rankings['b']['rating'] > updated_rankings['a']['rating']
+    assert unchanged_rankings['a']['rating'] > unchanged_rankings['b']['rating']
+
+    with pytest.raises(GlickoError):
+        test_period.remove_race(second_id)
+
+    # an update that fails leaves the race and its entrants alone
+    test_period.add_alias('b_alt', 'b')
+    with pytest.raises(GlickoError):
+        test_period.update_race(first_id, {'a': 1500, 'b': 1600, 'b_alt': 1700})
+
+    assert list(test_period.races.keys()) == [first_id]
+    assert test_period.rank() == updated_rankings
+
+def test_aliases_and_merging():
+    test_period = rr.MultiPeriod()
+    test_period.add_races([{'old_name': 1500, 'rival': 1600}])
+    test_period.rename_player('old_name', 'new_name')
+    test_period.add_races([{'old_name': 1500, 'rival': 1600}])
+
+    assert sorted(test_period.players) == ['new_name', 'rival']
+    assert test_period.aliases == {'old_name': 'new_name'}
+
+    test_period.add_races([{'alt_account': 1400, 'rival': 1600}])
+    test_period.merge_players('new_name', 'alt_account')
+    merged_rankings = test_period.rank()
+
+    assert 'alt_account' not in merged_rankings
+    assert test_period.aliases['alt_account'] == 'new_name'
+
+    combined_period = rr.MultiPeriod()
+    combined_period.add_races([{'new_name': 1500, 'rival': 1600},
+                               {'new_name': 1500, 'rival': 1600},
+                               {'new_name': 1400, 'rival': 1600}])
+
+    assert combined_period.rank()['new_name']['rating'] == pytest.approx(merged_rankings['new_name']['rating'])
+
+    with pytest.raises(GlickoError):
+        test_period.add_races([{'new_name': 1500, 'old_name': 1600}])
+    with pytest.raises(GlickoError):
+        test_period.rename_player('rival', 'new_name')
+
+    # an alias can only point at one player
+    test_period.add_alias('old_name', 'new_name')
+    with pytest.raises(GlickoError):
+        test_period.add_alias('old_name', 'rival')
+    assert test_period.aliases['old_name'] == 'new_name'
+
+    # taking back an old name drops it as an alias
+    test_period.rename_player('new_name', 'old_name')
+
+    assert sorted(test_period.players) == ['old_name', 'rival']
+    assert test_period.aliases == {'alt_account': 'old_name', 'new_name': 'old_name'}
+
+def test_provisional_status():
+    test_period = rr.MultiPeriod()
+    test_period.set_provisional_races(2)
+    test_period.set_provisional_deviation(250)
+    test_period.add_players({'veteran': {'rating': 1600,
+                                         'deviation': 80,
+                                         'volatility': .2,
+                                         'variance': 0,
+                                         'delta': 0,
+                                         'inactive_periods': 0,
+                                         'races': 20}})
+    test_period.add_races([{'veteran': 1500, 'newcomer': 1600, 'regular': 1550},
+                           {'veteran': 1500, 'regular': 1600}])
+    test_rankings = test_period.rank()
+
+    assert test_rankings['veteran']['races'] == 22
+    assert test_rankings['veteran']['provisional'] == 0
+    assert test_rankings['regular']['provisional'] == 0
+    assert test_rankings['newcomer']['provisional'] == 1
+    assert sorted(test_period.established()) == ['regular', 'veteran']
+
+    next_period = rr.MultiPeriod()
+    next_period.add_players(test_rankings)
+
+    assert next_period.rank()['regular']['races'] == 2
+
+def test_leaderboard():
+    test_period = rr.MultiPeriod()
+    test_period.set_score_k(1)
+    test_period.add_races([{'first': 1400, 'tied_1': 1500, 'tied_2': 1500, 'last': 1600}])
+    test_rankings = test_period.rank()
+
+    assert test_rankings['first']['score'] == pytest.approx(
+        test_rankings['first']['rating'] - test_rankings['first']['deviation'])
+    assert test_period.leaderboard() == []
+
+    board = test_period.leaderboard(provisional=True)
+    names = [name for name, _ in board]
+    positions = [entry['position'] for _, entry in board]
+
+    assert names == ['first', 'tied_1', 'tied_2', 'last']
+    assert positions == [1, 2, 2, 4]
+    assert board[1][1]['tied'] == 1 and board[0][1]['tied'] == 0
+    assert board[0][1]['percentile'] == 100
+    assert board[1][1]['percentile'] == pytest.approx(100 / 3)
+    assert board[3][1]['percentile'] == 0
+
+def test_confidence_intervals():
+    strong = {'rating': 1700, 'deviation': 50}
+    close = {'rating': 1690, 'deviation': 50}
+    weak = {'rating': 1400, 'deviation': 60}
+
+    low, high = rr.rating_interval(strong)
+
+    assert low == pytest.approx(1700 - 1.959964 * 50)
+    assert high == pytest.approx(1700 + 1.959964 * 50)
+    assert rr.rating_interval(strong, .5)[1] - rr.rating_interval(strong, .5)[0] < high - low
+    assert rr.probability_stronger(strong, strong) == pytest.approx(.5)
+    assert rr.probability_stronger(strong, weak) > .99
+    assert rr.probability_stronger(weak, strong) < .01
+    assert rr.distinguishable(strong, weak)
+    assert not rr.distinguishable(strong, close)
+
+    with pytest.raises(GlickoError):
+        rr.rating_interval(strong, 1.5)
+    with pytest.raises(GlickoError):
+        rr.probability_stronger(strong, {'rating': 1500})
+
+def test_volatility_convergence():
+    race = {'first': 1400, 'second': 1500, 'third': 1600}
+
+    test_period = rr.MultiPeriod()
+    test_period.add_races([race])
+    convergence = test_period.convergence()
+
+    assert all(p['converged'] == 1 for p in convergence.values())
+    assert all(0 < p['iterations'] < 100 for p in convergence.values())
+    assert all(0 <= p['bracketing_iterations'] < 100 for p in convergence.values())
+
+    # a delta this big overflows the volatility search
+    degenerate_period = rr.MultiPeriod()
+    degenerate_period.add_players({'first': {'rating': 1500, 'deviation': 300, 'volatility': .24,
+                                             'variance': 0, 'delta': 1e200,
+                                             'inactive_periods': 0}})
+    degenerate_period.add_races([race])
+    degenerate_rankings = degenerate_period.rank()
+    degenerate_convergence = degenerate_period.convergence()
+
+    assert degenerate_convergence['first']['converged'] == 0
+    assert degenerate_convergence['first']['error'] == 3
+    assert degenerate_rankings['first']['volatility'] == .24
+    assert all(not math.isnan(p['rating']) for p in degenerate_rankings.values())
+
+    with pytest.raises(GlickoError):
+        degenerate_period.set_glicko_tau(0)
+
+def test_ranking_from_another_thread():
+    import threading
+
+    period = rr.MultiPeriod()
+    races = [{'runner {}'.format(i): 1000 + (i * 7 + j * 13) % 300 for i in range(j % 5, 60)}
+             for j in range(40)]
+    period.add_races(races)
+    results = []
+
+    def rank():
+        for _ in range(20):
+            results.append(period.rank())
+
+    # rank() lets other threads run while it works, so the period changes
+    # under it. each call should still see a whole period
+    thread = threading.Thread(target=rank)
+    thread.start()
+    for _ in range(20):
+        period.add_races(races[:5])
+        period.remove_race(max(period.races))
+        period.next_period()
+    thread.join()
+
+    assert len(results) == 20
+    assert all(len(x) == 60 for x in results)
+
+def test_simulation():
+    reports = rr.simulate(players=150, periods=3, races=80, seed=7)
+
+    assert len(reports) == 3
+    assert reports[-1]['races'] == 240
+    assert all(0 < r['players'] <= 150 for r in reports)
+    assert reports[-1]['spearman'] > .7
+    assert reports[-1]['pearson'] > .7
+    assert rr.simulate(players=150, periods=3, races=80, seed=7)[-1]['spearman'] == \
+        pytest.approx(reports[-1]['spearman'])
+
+    with pytest.raises(GlickoError):
+        rr.simulate(players=10, max_size=20)
+    with pytest.raises(GlickoError):
+        rr.simulate(forfeit_rate=1)
+
+def test_backtest():
+    race = {'fast': 1400, 'middle': 1500, 'slow': 1600}
+    big_race = {'fast': 1400, 'middle': 1500, 'slow': 1600, 'forfeit': math.nan}
+    history = [[race, race, race], [race, big_race, race]]
+
+    results = rr.backtest(history)
+    first, second = results['periods']
+
+    assert first['races'] == 3 and first['pairs'] == 9
+    assert second['races'] == 3 and second['pairs'] == 12
+    # nobody is rated before the first race, so it's a coin flip
+    assert first['log_loss'] > second['log_loss']
+    assert second['log_loss'] < math.log(2)
+    assert second['brier'] < .25
+    assert second['top_1'] == 1
+    assert first['top_1'] == pytest.approx((1 / 3 + 1 + 1) / 3)
+    assert [s['size'] for s in results['sizes']] == [3, 4]
+    assert results['sizes'][0]['kendall_tau'] == pytest.approx(1)
+    assert results['sizes'][1]['races'] == 1
+    assert results['overall']['races'] == 6
+
+    established = {'fast': {'rating': 1800, 'deviation': 60, 'volatility': .06,
+                            'variance': 0, 'delta': 0, 'inactive_periods': 0}}
+    seeded = rr.backtest([[race]], players=established)
+    assert seeded['overall']['top_1'] == 1
+
+    # a period with no races has nothing to score
+    skipped = rr.backtest([[race], [], [race]])
+    empty = skipped['periods'][1]
+    assert empty['races'] == 0 and empty['pairs'] == 0
+    assert all(empty[k] is None for k in ('log_loss', 'brier', 'kendall_tau', 'top_1'))
+    assert skipped['overall']['races'] == 2
+    assert not math.isnan(skipped['overall']['log_loss'])
+
+def test_rating_distribution():
+    from statistics import NormalDist
+    normal = NormalDist(1500, 200)
+    ratings = [normal.inv_cdf((i + .5) / 500) for i in range(500)]
+    rankings = {str(i): {'rating': r, 'deviation': 80} for i, r in enumerate(ratings)}
+
+    diagnostics = rr.distribution(rankings, reference=ratings)
+
+    assert diagnostics['count'] == 500
+    assert diagnostics['mean'] == pytest.approx(1500)
+    assert diagnostics['stddev'] == pytest.approx(200, rel=.02)
+    assert diagnostics['p50'] == pytest.approx(1500)
+    assert diagnostics['p5'] < diagnostics['p25'] < diagnostics['p75'] < diagnostics['p95']
+    assert abs(diagnostics['skewness']) < .01
+    assert abs(diagnostics['kurtosis']) < .1
+    assert diagnostics['jarque_bera_p'] > .5
+    assert diagnostics['ks'] == 0 and diagnostics['ks_p'] == 1
+
+    skewed = [1500 + 200 * math.exp(r / 200 - 7.5) for r in ratings]
+    skewed_rankings = {str(i): {'rating': r} for i, r in enumerate(skewed)}
+    skewed_diagnostics = rr.distribution(skewed_rankings, reference=ratings)
+
+    assert skewed_diagnostics['skewness'] > 1
+    assert skewed_diagnostics['jarque_bera_p'] < .01
+    assert skewed_diagnostics['ks_p'] < .01
+    assert 'ks' not in rr.distribution(rankings)
+    assert rr.distribution(rankings, key='deviation')['stddev'] == 0
+
+    with pytest.raises(GlickoError):
+        rr.distribution({'a': {'rating': 1500}})
+    with pytest.raises(GlickoError):
+        rr.distribution(skewed_rankings, key='deviation')
+
+def test_presets_and_config_files(tmp_path):
+    alttpr_period = rr.MultiPeriod.from_preset('alttpr')
+    assert alttpr_period.constants == rr.MultiPeriod().constants
+
+    glicko_period = rr.MultiPeriod.from_preset('glicko2')
+    assert glicko_period.constants['tau'] == .5
+    assert glicko_period.constants['initial_deviation'] == 350
+    assert glicko_period.constants['norm_factor'] == alttpr_period.constants['norm_factor']
+
+    toml_path = tmp_path / 'category.toml'
+    toml_path.write_text('preset = "glicko2"\ntau = 0.3\nmulti_cutoff = 4\n')
+    toml_period = rr.MultiPeriod.from_config(str(toml_path))
+    assert toml_period.constants['tau'] == .3
+    assert toml_period.constants['multi_cutoff'] == 4
+    assert toml_period.constants['initial_deviation'] == 350
+
+    json_path = tmp_path / 'category.json'
+    toml_period.save_config(str(json_path))
+    assert rr.MultiPeriod.from_config(str(json_path)).constants == toml_period.constants
+
+    saved_toml_path = tmp_path / 'saved.toml'
+    toml_period.save_config(str(saved_toml_path))
+    assert rr.MultiPeriod.from_config(str(saved_toml_path)).constants == toml_period.constants
+
+    bad_path = tmp_path / 'bad.toml'
+    bad_path.write_text('taux = 0.3\n')
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_config(str(bad_path))
+    bad_path.write_text('casual_weight = -1\n')
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_config(str(bad_path))
+    for bad_constant in ('tau = -1', 'initial_deviation = 0', 'initial_volatility = 0',
+                         'multi_slope = 1', 'multi_slope = -0.1', 'norm_factor = -1',
+                         'victory_margin = -5', 'tau = nan', 'score_k = inf'):
+        bad_path.write_text(bad_constant + '\n')
+        with pytest.raises(GlickoError):
+            rr.MultiPeriod.from_config(str(bad_path))
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().set_multi_slope(1)
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_preset('not a game')
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_config(str(tmp_path / 'category.yaml'))
+
+def test_continuous_rating_periods():
+    day = 24 * 60 * 60
+    start = 1577836800
+    rated = {'rating': 1600, 'deviation': 80, 'volatility': .06, 'variance': 0,
+             'delta': 0, 'inactive_periods': 0, 'rated_at': start}
+    race = {'runner': 1400, 'opponent': 1500}
+
+    def deviation_after(days):
+        test_period = rr.MultiPeriod()
+        test_period.set_period_days(7)
+        test_period.add_players({'runner': dict(rated), 'opponent': dict(rated)})
+        test_period.add_races([race], timestamps=[start + days * day])
+        rankings = test_period.rank()
+        assert rankings['runner']['rated_at'] == start + days * day
+        return rankings['runner']['deviation']
+
+    assert deviation_after(3.5) < deviation_after(7) < deviation_after(28)
+
+    # without a period length every period counts the same
+    discrete_period = rr.MultiPeriod()
+    discrete_period.add_players({'runner': dict(rated), 'opponent': dict(rated)})
+    discrete_period.add_races([race], timestamps=[start + 28 * day])
+    assert discrete_period.rank()['runner']['deviation'] == pytest.approx(deviation_after(7))
+
+    idle_period = rr.MultiPeriod()
+    idle_period.set_period_days(7)
+    idle_period.add_players({'idle': dict(rated)})
+    week = idle_period.rank(at=start + 7 * day)['idle']
+    month = idle_period.rank(at=start + 28 * day)['idle']
+
+    assert week['rated_at'] == start + 7 * day
+    assert 80 < week['deviation'] < month['deviation']
+
+    # carrying the week's rating over doesn't count that week again
+    next_period = rr.MultiPeriod()
+    next_period.set_period_days(7)
+    next_period.add_players({'idle': week})
+    carried = next_period.rank(at=start + 7 * day)['idle']
+    assert carried['deviation'] == pytest.approx(week['deviation'])
+    assert carried['rating'] == pytest.approx(week['rating'])
+    assert carried['inactive_periods'] == week['inactive_periods']
+
+    # and neither does ranking again at the same time, however many times
+    for _ in range(3):
+        next_period = rr.MultiPeriod()
+        next_period.set_period_days(7)
+        next_period.add_players({'idle': carried})
+        carried = next_period.rank(at=start + 7 * day)['idle']
+    assert carried['rating'] == pytest.approx(week['rating'])
+
+    # a week away still decays a rating that's already inactive
+    later = next_period.rank(at=start + 14 * day)['idle']
+    assert later['rating'] < week['rating']
+    assert later['inactive_periods'] == pytest.approx(week['inactive_periods'] + 1)
+
+    # without any times to rate them as of, runners keep their rated_at
+    untimed_period = rr.MultiPeriod()
+    untimed_period.set_period_days(7)
+    untimed_period.add_players({'idle': dict(rated)})
+    assert untimed_period.rank()['idle']['rated_at'] == start
+
+    with pytest.raises(GlickoError):
+        idle_period.add_races([race, race], timestamps=[start])
+    with pytest.raises(GlickoError):
+        idle_period.set_period_days(-1)
+
+def test_mid_period_updates():
+    established = {'rating': 1600, 'deviation': 80, 'volatility': .06, 'variance': 0,
+                   'delta': 0, 'inactive_periods': 0}
+    first_race = {'established': 1400, 'newcomer': 1500}
+    second_race = {'established': 1450, 'newcomer': 1420, 'late': 1600}
+
+    test_period = rr.MultiPeriod()
+    test_period.add_players({'established': dict(established), 'idle': dict(established)})
+    after_first = test_period.update([first_race])
+
+    assert sorted(after_first) == ['established', 'newcomer']
+    assert after_first['established']['rating'] == pytest.approx(
+        test_period.rank()['established']['rating'])
+
+    after_second = test_period.update([second_race])
+    full_period = rr.MultiPeriod()
+    full_period.add_players({'established': dict(established), 'idle': dict(established)})
+    full_period.add_races([first_race, second_race])
+    full_rankings = full_period.rank()
+
+    # every race is counted exactly once no matter how often we update
+    assert sorted(after_second) == ['established', 'late', 'newcomer']
+    for name in after_second:
+        assert after_second[name]['rating'] == pytest.approx(full_rankings[name]['rating'])
+        assert after_second[name]['deviation'] == pytest.approx(full_rankings[name]['deviation'])
+
+    test_period.next_period()
+    next_period = rr.MultiPeriod()
+    next_period.add_players(full_rankings)
+
+    assert test_period.races == {}
+    assert sorted(test_period.players) == sorted(next_period.players)
+    closed_rankings = test_period.rank()
+    for name, player in next_period.rank().items():
+        assert closed_rankings[name]['rating'] == pytest.approx(player['rating'])
+        assert closed_rankings[name]['deviation'] == pytest.approx(player['deviation'])
+        assert closed_rankings[name]['inactive_periods'] == player['inactive_periods']
+        assert closed_rankings[name]['races'] == player['races']
+
+    with pytest.raises(GlickoError):
+        test_period.update([first_race, {'alone': 1000}])
+    assert test_period.races == {}
+
+def test_ledger_replay(tmp_path):
+    test_period = rr.MultiPeriod()
+    test_period.set_period_days(7)
+    test_period.add_players({'veteran': {'rating': 1600, 'deviation': 80, 'volatility': .06,
+                                         'variance': 0, 'delta': 0, 'inactive_periods': 0}})
+    test_period.add_races([{'veteran': 1400, 'rookie': 1500, 'quitter': math.nan},
+                           {'veteran': 1450, 'rookie': 1420}],
+                          timestamps=[1580515200, 1580601600])
+    test_period.add_alias('vet', 'veteran')
+    test_period.add_races([{'vet': 1500, 'rookie': 1440}], event='qualifier')
+    first_rankings = test_period.rank()
+    test_period.next_period()
+    test_period.add_races([{'rookie': 1390, 'quitter': 1420}])
+    rankings = test_period.rank()
+
+    ledger = test_period.ledger
+    assert [x['seq'] for x in ledger] == list(range(len(ledger)))
+    assert all(x['previous'] == y['hash'] for x, y in zip(ledger[1:], ledger))
+    assert [x['event'] for x in ledger].count('rank') == 2
+
+    path = str(tmp_path / 'ledger.jsonl')
+    test_period.save_ledger(path)
+    replayed = rr.MultiPeriod.from_ledger(path)
+
+    # replaying gives back the same period, down to the last hash
+    assert [x['hash'] for x in replayed.ledger] == [x['hash'] for x in ledger]
+    assert replayed.rank() == rankings
+    assert replayed.aliases == {'vet': 'veteran'}
+    assert first_rankings != rankings
+
+    # changing anything in a saved ledger is caught
+    with open(path) as f:
+        lines = f.readlines()
+    with open(path, 'w') as f:
+        f.writelines(lines[:2] + [lines[2].replace('1400', '1300')] + lines[3:])
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_ledger(path)
+    with open(path, 'w') as f:
+        f.writelines(lines[:3] + lines[4:])
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod.from_ledger(path)
+
+@pytest.mark.skipif(not hasattr(rr, 'League'), reason='built without the sqlite feature')
+def test_sqlite_league(tmp_path):
+    path = str(tmp_path / 'league.db')
+    player = {'rating': 1600, 'deviation': 80, 'volatility': .06, 'variance': 0,
+              'delta': 0, 'inactive_periods': 0}
+    races = [{'veteran': 1400, 'rookie': 1500, 'quitter': math.nan},
+             {'veteran': 1450, 'rookie': 1420}]
+
+    league = rr.League(path)
+    league.set_constants({'tau': .2, 'multi_slope': .008, 'multi_cutoff': 6,
+                          'norm_factor': 1.3, 'victory_margin': 600,
+                          'initial_rating': 1500, 'initial_deviation': 300,
+                          'initial_volatility': .23})
+    league.add_players({'veteran': player})
+    league.add_races(races[:1])
+    assert league.period == 1
+
+    # a reopened league picks up where the last one left off
+    league = rr.League(path)
+    league.add_races(races[1:], event='qualifier')
+    with pytest.raises(GlickoError):
+        league.add_races([{'alone': 1000}])
+
+    test_period = rr.MultiPeriod()
+    test_period.set_constants(league.current_period().constants)
+    test_period.add_players({'veteran': player})
+    test_period.add_races(races[:1])
+    test_period.add_races(races[1:], event='qualifier')
+    expected = test_period.rank()
+
+    assert league.current_period().rank() == expected
+    closed = league.close_period()
+    assert closed == expected
+    assert league.period == 2
+    assert league.rankings() == closed
+    assert league.current_period().races == {}
+    with pytest.raises(GlickoError):
+        league.rankings(2)
+    with pytest.raises(GlickoError):
+        league.rankings(0)
+
+    next_period = rr.MultiPeriod()
+    next_period.set_constants(league.current_period().constants)
+    next_period.add_players(closed)
+    assert rr.League(path).current_period().rank() == next_period.rank()
+
+    # players added later join the open period without touching closed ones
+    league.add_players({'latecomer': player})
+    assert league.rankings(1) == closed
+    next_period.add_players({'latecomer': player})
+    assert rr.League(path).current_period().rank() == next_period.rank()
+    assert 'latecomer' in league.close_period(at=1580601600)
+    assert league.rankings(1) == closed
+
+    # closed periods record when they were rated as of
+    import sqlite3
+    closed_at = dict(sqlite3.connect(path).execute('SELECT id, closed_at FROM periods'))
+    assert closed_at[1] is not None
+    assert closed_at[2] == 1580601600
+    assert closed_at[3] is None
+
+def test_race_tables():
+    from array import array
+
+    races = [{'veteran': 1400, 'rookie': 1500, 'quitter': math.nan},
+             {'veteran': 1450, 'rookie': 1420}]
+    columns = {'race_id': ['a', 'b', 'a', 'a', 'b'],
+               'player': ['veteran', 'veteran', 'rookie', 'quitter', 'rookie'],
+               'time': [1400, 1450, 1500, None, 1420],
+               'timestamp': [1580515200, 1580601600, 1580515200, 1580515200, 1580601600]}
+
+    class Series:
+        # stands in for a pandas series, numeric ones having an array
+        def __init__(self, values):
+            self.values = values
+
+        def to_numpy(self, na_value=None):
+            if all(isinstance(x, str) for x in self.values):
+                return self
+            return array('d', [math.nan if x is None else x for x in self.values])
+
+        def tolist(self):
+            return self.values
+
+    names = Series(list(columns))
+
+    class DataFrame:
+        # stands in for a pandas dataframe
+        columns = names
+
+        def __getitem__(self, name):
+            return Series(columns[name])
+
+        def to_numpy(self):
+            raise NotImplementedError
+
+    list_period = rr.MultiPeriod()
+    list_period.add_races(races, timestamps=[1580515200, 1580601600])
+    dict_period = rr.MultiPeriod()
+    dict_period.add_races(columns)
+    table_period = rr.MultiPeriod()
+    table_period.add_races(DataFrame())
+    # a dict that isn't all lists is a single race, not a table
+    race_period = rr.MultiPeriod()
+    race_period.add_races({'race_id': 1400, 'player': 1500})
+    assert race_period.races == {0: {'race_id': 1400, 'player': 1500}}
+
+    # forfeits come through as NaN, which never equals itself
+    dict_races = dict_period.races
+    assert math.isnan(dict_races[0].pop('quitter'))
+    assert dict_races == {0: {'veteran': 1400, 'rookie': 1500},
+                          1: {'veteran': 1450, 'rookie': 1420}}
+    assert table_period.rank() == list_period.rank()
+
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().add_races({'race_id': [1, 1], 'player': ['veteran']})
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().add_races({'race_id': [1, 1], 'player': ['veteran', 'veteran'],
+                                    'time': [1400, 1500]})
+
+    try:
+        import pandas
+    except ImportError:
+        pass
+    else:
+        # nullable integers and datetimes, which numpy holds in nanoseconds
+        frame = pandas.DataFrame(columns)
+        frame['time'] = frame['time'].astype('Int64')
+        frame['timestamp'] = pandas.to_datetime(frame['timestamp'], unit='s')
+        pandas_period = rr.MultiPeriod()
+        pandas_period.add_races(frame)
+        assert pandas_period.rank() == list_period.rank()
+
+    try:
+        import pyarrow
+    except ImportError:
+        return
+    arrow_period = rr.MultiPeriod()
+    arrow_period.add_races(pyarrow.table(columns))
+    assert arrow_period.rank() == list_period.rank()
+    timestamps = pyarrow.array(columns['timestamp'], pyarrow.timestamp('s'))
+    arrow_period = rr.MultiPeriod()
+    arrow_period.add_races(pyarrow.table(dict(columns, timestamp=timestamps)))
+    assert arrow_period.rank() == list_period.rank()
+
+    batch = list_period.rank(arrow=True)
+    rankings = list_period.rank()
+    assert isinstance(batch, pyarrow.RecordBatch)
+    assert batch.column('player').to_pylist() == sorted(rankings)
+    assert batch.column('rating').to_pylist() == [rankings[x]['rating'] for x in sorted(rankings)]
+
+def test_race_arrays():
+    from array import array
+
+    names = ['veteran', 'rookie', 'quitter']
+    players = array('q', [0, 1, 2, 0, 1])
+    offsets = array('q', [0, 3, 5])
+    times = array('d', [1400, 1500, math.nan, 1450, 1420])
+    timestamps = array('d', [1580515200, 1580601600])
+
+    array_period = rr.MultiPeriod()
+    ids = array_period.add_race_arrays(names, players, offsets, times, event='qualifier',
+                                       timestamps=timestamps)
+    list_period = rr.MultiPeriod()
+    list_period.add_races([{'veteran': 1400, 'rookie': 1500, 'quitter': math.nan},
+                           {'veteran': 1450, 'rookie': 1420}],
+                          event='qualifier', timestamps=[1580515200, 1580601600])
+
+    assert ids == [0, 1]
+    assert array_period.rank() == list_period.rank()
+
+    # 32 bit indices work too
+    small_period = rr.MultiPeriod()
+    small_period.add_race_arrays(names, array('i', players), array('i', offsets), times,
+                                 event='qualifier', timestamps=timestamps)
+    assert small_period.rank() == list_period.rank()
+
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().add_race_arrays(names, players, array('q', [0, 3]), times)
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().add_race_arrays(names, array('q', [0, 1, 3, 0, 1]), offsets, times)
+    with pytest.raises(GlickoError):
+        rr.MultiPeriod().add_race_arrays(names, players, offsets, array('q', [1, 2, 3, 4, 5]))
+
+def test_type_stubs():
+    # everything in randorank.pyi exists, has the same arguments and is documented
+    stub_path = os.path.join(os.path.dirname(__file__), '..', 'randorank.pyi')
+    with open(stub_path) as f:
+        stub = ast.parse(f.read())
+
+    def check(runtime, stub_def):
+        if any(getattr(x, 'id', None) == 'property' for x in stub_def.decorator_list):
+            return
+        args = [a.arg for a in stub_def.args.args + stub_def.args.kwonlyargs]
+        params = list(inspect.signature(runtime).parameters)
+        if args[:1] == ['self'] and params[:1] == ['self']:
+            args, params = args[1:], params[1:]
+        assert set(args) <= set(params), stub_def.name
+        assert runtime.__doc__
+
+    for node in stub.body:
+        if isinstance(node, ast.FunctionDef):
+            check(getattr(rr, node.name), node)
+        elif isinstance(node, ast.ClassDef) and node.name in ('MultiPeriod', 'League'):
+            if not hasattr(rr, node.name):
+                continue
+            cls = getattr(rr, node.name)
+            assert cls.__doc__
+            for method in node.body:
+                if isinstance(method, ast.FunctionDef) and method.name != '__init__':
+                    assert hasattr(cls, method.name), method.name
+                    check(getattr(cls, method.name), method)